@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+// Aho–Corasick over bytes. The trie is completed into a DFA when built, so a scan
+// is one table lookup per byte of text.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    next: Vec<[u32; 256]>,
+    // length of the trie path to each state
+    depth: Vec<usize>,
+    // patterns ending at each state, following the failure links
+    outputs: Vec<Vec<usize>>,
+    lens: Vec<usize>,
+}
+
+// Pattern `pattern` found at `start..end` of the text, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+const ROOT: u32 = 0;
+
+impl AhoCorasick {
+    // Empty patterns never match.
+    pub fn new<P: AsRef<[u8]>>(patterns: &[P]) -> AhoCorasick {
+        let mut ac = AhoCorasick {
+            next: vec![[ROOT; 256]],
+            depth: vec![0],
+            outputs: vec![Vec::new()],
+            lens: patterns.iter().map(|p| p.as_ref().len()).collect(),
+        };
+
+        // trie, 0 standing for "no child" as the root is never a child
+        for (i, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_ref();
+            if pattern.is_empty() {
+                continue;
+            }
+            let mut state = ROOT as usize;
+            for b in pattern {
+                if ac.next[state][*b as usize] == ROOT {
+                    ac.next.push([ROOT; 256]);
+                    ac.depth.push(ac.depth[state] + 1);
+                    ac.outputs.push(Vec::new());
+                    ac.next[state][*b as usize] = (ac.next.len() - 1) as u32;
+                }
+                state = ac.next[state][*b as usize] as usize;
+            }
+            ac.outputs[state].push(i);
+        }
+
+        // breadth first, so failure targets are complete before they're used
+        let mut fail = vec![ROOT; ac.next.len()];
+        let mut queue: VecDeque<usize> = ac.next[ROOT as usize].iter()
+            .filter(|s| **s != ROOT)
+            .map(|s| *s as usize)
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            let inherited = ac.outputs[fail[state] as usize].clone();
+            ac.outputs[state].extend(inherited);
+            for b in 0..256 {
+                let child = ac.next[state][b];
+                let fallback = ac.next[fail[state] as usize][b];
+                if child == ROOT {
+                    ac.next[state][b] = fallback;
+                } else {
+                    fail[child as usize] = fallback;
+                    queue.push_back(child as usize);
+                }
+            }
+        }
+
+        ac
+    }
+
+    // The match starting first, the longest one among those starting there. Stops
+    // as soon as no partial match could start early enough to beat it.
+    pub fn leftmost_longest(&self, text: &[u8]) -> Option<Match> {
+        let mut best: Option<Match> = None;
+        let mut state = ROOT as usize;
+        for (i, b) in text.iter().enumerate() {
+            state = self.next[state][*b as usize] as usize;
+            for p in &self.outputs[state] {
+                let m = self.at(*p, i + 1);
+                if best.is_none_or(|b| (m.start, b.end) < (b.start, m.end)) {
+                    best = Some(m);
+                }
+            }
+            if best.is_some_and(|b| i + 1 - self.depth[state] > b.start) {
+                break;
+            }
+        }
+        best
+    }
+
+    fn at(&self, pattern: usize, end: usize) -> Match {
+        Match { pattern, start: end - self.lens[pattern], end }
+    }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_failure_links() {
+        let ac = AhoCorasick::new(&["he", "she", "his", "hers"]);
+        assert_eq!(Some(Match { pattern: 1, start: 1, end: 4 }), ac.leftmost_longest(b"ushers"));
+        assert_eq!(Some(Match { pattern: 3, start: 2, end: 6 }), ac.leftmost_longest(b"shhers"));
+        assert_eq!(None, ac.leftmost_longest(b""));
+    }
+
+    #[test]
+    fn leftmost_longest_exits_early() {
+        let ac = AhoCorasick::new(&["v", "vi", "vii", "viii", "i", "abcde", "c"]);
+        assert_eq!(Some(Match { pattern: 3, start: 1, end: 5 }), ac.leftmost_longest(b"xviiix"));
+        assert_eq!(Some(Match { pattern: 5, start: 0, end: 5 }), ac.leftmost_longest(b"abcde"));
+        assert_eq!(Some(Match { pattern: 6, start: 2, end: 3 }), ac.leftmost_longest(b"abcdv"));
+        assert_eq!(None, ac.leftmost_longest(b"xyz"));
+        assert_eq!(None, AhoCorasick::new(&[""]).leftmost_longest(b"abc"));
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+// What every part computes. 128 bits leave room for sums and products of the
+// u64/i64 values the puzzles deal in, and the helpers below report the cases that
+// still don't fit instead of wrapping in release builds.
+pub type Answer = i128;
+
+pub type Checked = Result<Answer, Overflow>;
+
+// Names the operation that overflowed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overflow(pub &'static str);
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "answer overflows in {}", self.0)
+    }
+}
+
+// For `?` in the parts, which report errors as text.
+impl From<Overflow> for String {
+    fn from(e: Overflow) -> String {
+        e.to_string()
+    }
+}
+
+pub fn of<T: TryInto<Answer>>(value: T) -> Checked {
+    value.try_into().map_err(|_| Overflow("conversion"))
+}
+
+pub fn add(a: Answer, b: Answer) -> Checked {
+    a.checked_add(b).ok_or(Overflow("addition"))
+}
+
+pub fn sub(a: Answer, b: Answer) -> Checked {
+    a.checked_sub(b).ok_or(Overflow("subtraction"))
+}
+
+pub fn mul(a: Answer, b: Answer) -> Checked {
+    a.checked_mul(b).ok_or(Overflow("multiplication"))
+}
+
+pub fn pow(base: Answer, exp: u32) -> Checked {
+    base.checked_pow(exp).ok_or(Overflow("power"))
+}
+
+pub fn sum<T: TryInto<Answer>>(values: impl IntoIterator<Item = T>) -> Checked {
+    values.into_iter().try_fold(0, |acc, v| add(acc, of(v)?))
+}
+
+pub fn product<T: TryInto<Answer>>(values: impl IntoIterator<Item = T>) -> Checked {
+    values.into_iter().try_fold(1, |acc, v| mul(acc, of(v)?))
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_helpers() {
+        assert_eq!(Ok(6), sum([1u64, 2, 3]));
+        assert_eq!(Ok(-6), product([-1i64, 2, 3]));
+        assert_eq!(Ok(u64::MAX as Answer * 2), sum([u64::MAX, u64::MAX]));
+        assert_eq!(Err(Overflow("addition")), add(Answer::MAX, 1));
+        assert_eq!(Err(Overflow("multiplication")), product([u64::MAX; 3]));
+        assert_eq!(Err(Overflow("conversion")), of(u128::MAX));
+        assert_eq!(Ok(1 << 100), pow(2, 100));
+    }
+}
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+use std::fmt;
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::Pair;
+use pest::{Parser, RuleType, Span};
+use serde::Serialize;
+use crate::diagnostic::{Diagnostic, Diagnostics};
+
+// Builds a typed value out of a matched grammar rule. Structs get their impl
+// through `from_pair!`, leaves (strings and numbers) are implemented below.
+pub trait FromPair<'i, R: RuleType>: Sized {
+    fn from_pair(pair: Pair<'i, R>) -> Result<Self, Diagnostic>;
+}
+
+impl<'i, R: RuleType> FromPair<'i, R> for &'i str {
+    fn from_pair(pair: Pair<'i, R>) -> Result<Self, Diagnostic> {
+        Ok(pair.as_str())
+    }
+}
+
+impl<'i, R: RuleType> FromPair<'i, R> for Cow<'i, str> {
+    fn from_pair(pair: Pair<'i, R>) -> Result<Self, Diagnostic> {
+        Ok(Cow::Borrowed(pair.as_str()))
+    }
+}
+
+macro_rules! from_pair_number {
+    ($($t:ty),*) => ($(
+        impl<'i, R: RuleType> FromPair<'i, R> for $t {
+            fn from_pair(pair: Pair<'i, R>) -> Result<Self, Diagnostic> {
+                pair.as_str().trim().parse::<$t>()
+                    .map_err(|e| span_error::<R>(pair.as_span(), format!("invalid {} '{}': {}", stringify!($t), pair.as_str(), e)))
+            }
+        }
+    )*)
+}
+
+from_pair_number!(i32, i64, u32, u64, usize);
+
+pub fn span_error<R: RuleType>(span: Span<'_>, message: String) -> Diagnostic {
+    Error::<R>::new_from_span(ErrorVariant::CustomError { message }, span).into()
+}
+
+// Error pointing at `slice`, which has to be borrowed from `input` (as `&'i str` leaves are).
+pub fn slice_error<R: RuleType>(input: &str, slice: &str, message: String) -> Diagnostic {
+    let start = slice.as_ptr() as usize - input.as_ptr() as usize;
+    span_error::<R>(Span::new(input, start, start + slice.len()).unwrap(), message)
+}
+
+// Parses `input` with `rule` and maps the top pair to `T`.
+pub fn parse<'i, P: Parser<R>, R: RuleType, T: FromPair<'i, R>>(rule: R, input: &'i str) -> Result<T, Diagnostic> {
+    let pair = P::parse(rule, input)?.next().unwrap();
+    T::from_pair(pair)
+}
+
+// Parses `input` with `rule` and maps every direct child matching `item` to `T`.
+pub fn parse_children<'i, P: Parser<R>, R: RuleType, T: FromPair<'i, R>>(rule: R, item: R, input: &'i str) -> Result<Vec<T>, Diagnostic> {
+    P::parse(rule, input)?.next().unwrap()
+        .into_inner()
+        .filter(|r| r.as_rule() == item)
+        .map(T::from_pair)
+        .collect()
+}
+
+// Parses every non-blank line of `input` on its own with `record` (a rule wrapping
+// the item in SOI/EOI), so one malformed line doesn't hide the ones after it.
+pub fn parse_records<'i, P: Parser<R>, R: RuleType, T: FromPair<'i, R>>(record: R, input: &'i str) -> Records<T> {
+    let mut records = Records { values: Vec::new(), errors: Vec::new() };
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = P::parse(record, line)
+            .map_err(Diagnostic::from)
+            .and_then(|mut pairs| T::from_pair(pairs.next().unwrap()));
+        match parsed {
+            Ok(value) => records.values.push(value),
+            Err(mut d) => {
+                d.line = i + 1;
+                records.errors.push(d);
+            }
+        }
+    }
+
+    records
+}
+
+pub struct Records<T> {
+    pub values: Vec<T>,
+    pub errors: Vec<Diagnostic>,
+}
+
+impl<T> Records<T> {
+    // Strict mode fails with every malformed record, lenient mode skips them with a warning.
+    pub fn accept(self, lenient: bool) -> Result<Vec<T>, Diagnostics> {
+        if self.errors.is_empty() {
+            return Ok(self.values);
+        }
+        if !lenient {
+            return Err(Diagnostics(self.errors));
+        }
+
+        for d in &self.errors {
+            eprintln!("warning: skipping malformed record\n{}", d);
+        }
+        eprintln!("warning: skipped {} malformed record(s)", self.errors.len());
+        Ok(self.values)
+    }
+}
+
+// Renders a parsed input for `aoc2023 parse`, as JSON or as the Debug tree.
+pub fn dump<T: Serialize + fmt::Debug>(value: &T, json: bool) -> String {
+    if json {
+        serde_json::to_string_pretty(value).unwrap()
+    } else {
+        format!("{:#?}", value)
+    }
+}
+
+// FIELD KINDS ----------------------------------
+
+pub fn one<R: RuleType, T>(mut values: Vec<T>, field: &str, span: Span<'_>) -> Result<T, Diagnostic> {
+    match values.len() {
+        1 => Ok(values.pop().unwrap()),
+        n => Err(span_error::<R>(span, format!("expected exactly one '{}', found {}", field, n))),
+    }
+}
+
+// `R` is unused but keeps the signature in line with the other kinds for `from_pair!`.
+#[allow(clippy::extra_unused_type_parameters)]
+pub fn many<R: RuleType, T>(values: Vec<T>, _field: &str, _span: Span<'_>) -> Result<Vec<T>, Diagnostic> {
+    Ok(values)
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// On-disk cache of what a day builds out of its input, so later runs can skip
+// parsing and preprocessing. Entries are named after a hash of the input and the
+// crate version: editing either just misses the old entries.
+
+// FNV-1a, 64 bits.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn key(content: &str) -> u64 {
+    let version = env!("CARGO_PKG_VERSION");
+    fnv1a(format!("{}\0{}", version, content).as_bytes())
+}
+
+pub fn entry_path(dir: &Path, name: &str, content: &str) -> PathBuf {
+    dir.join(format!("{}-{:016x}.bin", name, key(content)))
+}
+
+// Reads the entry for `content` from `dir`, or builds the value and writes it.
+// Without a directory this is just `build()`. A cache that can't be read or
+// written only costs a warning, build errors are returned as they are.
+pub fn load_or_build<T, E>(dir: Option<&Path>, name: &str, content: &str, build: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where T: Serialize + DeserializeOwned {
+    let Some(dir) = dir else {
+        return build();
+    };
+
+    let path = entry_path(dir, name, content);
+    if let Ok(bytes) = fs::read(&path) {
+        match bincode::deserialize(&bytes) {
+            Ok(value) => return Ok(value),
+            Err(e) => eprintln!("warning: ignoring unreadable cache entry {}: {}", path.display(), e),
+        }
+    }
+
+    let value = build()?;
+    let stored = bincode::serialize(&value).map_err(|e| e.to_string())
+        .and_then(|bytes| {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            fs::write(&path, bytes).map_err(|e| e.to_string())
+        });
+    if let Err(e) = stored {
+        eprintln!("warning: couldn't write cache entry {}: {}", path.display(), e);
+    }
+    Ok(value)
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv_reference_values() {
+        assert_eq!(0xcbf29ce484222325, fnv1a(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, fnv1a(b"a"));
+        assert_eq!(0x85944171f73967e8, fnv1a(b"foobar"));
+        assert_ne!(key("a"), key("b"));
+    }
+
+    #[test]
+    fn builds_once_then_reads() {
+        let dir = std::env::temp_dir().join(format!("aoc2023-cache-test-{}", std::process::id()));
+        let mut builds = 0;
+        let mut load = |content: &str| load_or_build(Some(&dir), "test", content, || {
+            builds += 1;
+            Ok::<_, String>(vec![content.len() as u64, 42])
+        });
+        assert_eq!(Ok(vec![3, 42]), load("abc"));
+        assert_eq!(Ok(vec![3, 42]), load("abc"));
+        assert_eq!(Ok(vec![4, 42]), load("abcd"));
+        assert_eq!(2, builds);
+
+        fs::write(entry_path(&dir, "test", "abc"), b"\xff").unwrap();
+        assert_eq!(Ok(vec![3, 42]), load_or_build(Some(&dir), "test", "abc", || Ok::<_, String>(vec![3, 42])));
+        assert_eq!(Err("bad".to_string()), load_or_build::<Vec<u64>, _>(None, "test", "abc", || Err("bad".to_string())));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
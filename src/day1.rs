@@ -1,89 +1,496 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::time::Instant;
+use rayon::prelude::*;
+use pest_derive::Parser;
+use crate::aho_corasick::{AhoCorasick, Match};
+use crate::answer::{self, Answer, Overflow};
+use crate::ast;
+use crate::diagnostic::Diagnostic;
+use crate::options::Options;
 
+// PARSING -------------------------------------
 
-fn part1_inner(content: String) -> i32 {
-    content.split("\n")
-        .into_iter()
-        .map(|v| match (v.find(|c: char| c.is_ascii_digit()), v.rfind(|c: char| c.is_ascii_digit())) {
-            (Some(d), Some(rd)) => (v[d..=d].to_string() + &v[rd..=rd].to_string()).parse(),
-            (None, None) => Ok(0),
-            _ => panic!("how!?")
-        }.unwrap())
-        .sum()
+#[derive(Parser)]
+#[grammar = "./day1_grammar.pest"]
+pub struct Document;
+
+fn parse(content: &str) -> Result<Vec<&str>, Diagnostic> {
+    ast::parse_children::<Document, _, _>(Rule::file, Rule::line, content)
 }
 
+pub fn dump(content: &str, _options: &Options, json: bool) -> Result<String, String> {
+    let lines = parse(content).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&lines, json))
+}
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(content));
+// A digit or number word found at `start..end` of its line, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Token {
+    value: u8,
+    start: usize,
+    end: usize,
 }
 
-// -------------------------------------
+// The first and the last token of a line, the same one when it has a single token.
+type Extract<'a> = &'a dyn Fn(&str) -> Option<(Token, Token)>;
 
-const NUMBERS: [(&'static str, &'static str); 10] = [
-    ("0", "zero"), ("1", "one"), ("2", "two"), ("3", "three"), ("4", "four"),
-    ("5", "five"), ("6", "six"), ("7", "seven"), ("8", "eight"), ("9", "nine")
-];
+fn calibration_value((first, last): (Token, Token)) -> Answer {
+    first.value as Answer * 10 + last.value as Answer
+}
 
-fn matches_digit(v: &str) -> Option<&str> {
-    if v.chars().next().unwrap().is_ascii_digit() {
-        return Some(&v[0..1]);
-    }
+// Sum of the calibration values, and the numbers of the lines that have none.
+#[derive(Debug, PartialEq)]
+struct Calibration {
+    sum: Answer,
+    undigited: Vec<usize>,
+}
 
-    for i in 0..10 {
-        let (d, num) = NUMBERS[i];
-        if v.starts_with(num) {
-            return Some(d);
+// Blank lines are skipped rather than reported.
+fn calibrate(lines: &[&str], extract: Extract) -> Result<Calibration, Overflow> {
+    let mut calibration = Calibration { sum: 0, undigited: Vec::new() };
+    for (i, line) in lines.iter().copied().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match extract(line) {
+            Some(tokens) => calibration.sum = answer::add(calibration.sum, calibration_value(tokens))?,
+            None => calibration.undigited.push(i + 1),
         }
     }
+    Ok(calibration)
+}
 
-    None
+fn print_calibration(calibration: Result<Calibration, Overflow>) -> Result<(), String> {
+    let calibration = calibration.map_err(|e| e.to_string())?;
+    println!("result: {}", calibration.sum);
+    if !calibration.undigited.is_empty() {
+        let lines: Vec<String> = calibration.undigited.iter().map(|l| l.to_string()).collect();
+        eprintln!("warning: {} line(s) without a digit, left out of the sum: {}", lines.len(), lines.join(", "));
+    }
+    Ok(())
+}
+
+// PART 1 --------------------------------------
+
+// ASCII or full-width digit.
+fn digit_value(c: char) -> Option<u8> {
+    match c {
+        '0'..='9' => Some(c as u8 - b'0'),
+        '０'..='９' => Some((c as u32 - '０' as u32) as u8),
+        _ => None
+    }
+}
+
+fn digit_token((start, c): (usize, char)) -> Option<Token> {
+    digit_value(c).map(|value| Token { value, start, end: start + c.len_utf8() })
+}
+
+fn part1_tokens(line: &str) -> Option<(Token, Token)> {
+    let first = line.char_indices().find_map(digit_token)?;
+    let last = line.char_indices().rev().find_map(digit_token)?;
+    Some((first, last))
 }
 
-pub fn part2_inner(content: String) -> i32 {
-    let mut sum = 0;
-    for line in content.split("\n") {
-        let mut ld = None;
-        for i in 0..line.len() {
-            if let Some(x) = matches_digit(&line[i..]) {
-                ld = Some(x);
-                break;
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    let lines = parse(&content).map_err(|d| d.to_string())?;
+    if options.explain {
+        print!("{}", explain(&lines, &part1_tokens, std::io::stdout().is_terminal()));
+    }
+    print_calibration(calibrate(&lines, &part1_tokens))
+}
+
+// PART 2 --------------------------------------
+
+// Number words by value, the digits themselves always count too.
+const ENGLISH: [&str; 10] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+const FRENCH: [&str; 10] = ["zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf"];
+const GERMAN: [&str; 10] = ["null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun"];
+const ROMAN: [&str; 10] = ["", "i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix"];
+
+// Tokens standing for a digit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocabulary {
+    tokens: Vec<(String, u8)>,
+}
+
+impl Vocabulary {
+    fn from_words(words: &[&str]) -> Vocabulary {
+        let tokens = words.iter().enumerate()
+            .filter(|(_, w)| !w.is_empty())
+            .map(|(d, w)| (w.to_string(), d as u8))
+            .collect();
+        Vocabulary::with_digits(tokens)
+    }
+
+    fn with_digits(mut tokens: Vec<(String, u8)>) -> Vocabulary {
+        for d in 0..10 {
+            tokens.push((d.to_string(), d));
+            tokens.push((char::from_u32('０' as u32 + d as u32).unwrap().to_string(), d));
+        }
+        Vocabulary { tokens }
+    }
+
+    // `english`, `french`, `german`, `roman`, or else the path of a file with one
+    // `token digit` pair per line, `#` starting a comment.
+    pub fn load(name: &str) -> Result<Vocabulary, String> {
+        match name {
+            "english" => Ok(Vocabulary::from_words(&ENGLISH)),
+            "french" => Ok(Vocabulary::from_words(&FRENCH)),
+            "german" => Ok(Vocabulary::from_words(&GERMAN)),
+            "roman" => Ok(Vocabulary::from_words(&ROMAN)),
+            path => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("unknown vocabulary '{}' ({})", path, e))?;
+                Vocabulary::parse(&text).map_err(|e| format!("{}: {}", path, e))
             }
         }
+    }
 
-        if ld == None {
+    pub fn parse(text: &str) -> Result<Vocabulary, String> {
+        let mut tokens = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parsed = line.rsplit_once(char::is_whitespace)
+                .and_then(|(token, digit)| Some((token.trim(), digit.parse::<u8>().ok().filter(|d| *d < 10)?)));
+            match parsed {
+                Some((token, digit)) => tokens.push((token.to_string(), digit)),
+                None => return Err(format!("line {}: expected 'token digit', got '{}'", i + 1, line)),
+            }
+        }
+        Ok(Vocabulary::with_digits(tokens))
+    }
+}
+
+// Finds the first token of a line with one automaton and the last one with
+// another over the reversed tokens, run on the reversed line.
+pub struct Extractor {
+    forward: AhoCorasick,
+    backward: AhoCorasick,
+    values: Vec<u8>,
+}
+
+impl Extractor {
+    pub fn new(vocabulary: &Vocabulary) -> Extractor {
+        let tokens: Vec<&[u8]> = vocabulary.tokens.iter().map(|(t, _)| t.as_bytes()).collect();
+        let reversed: Vec<Vec<u8>> = tokens.iter().map(|t| t.iter().rev().copied().collect()).collect();
+        Extractor {
+            forward: AhoCorasick::new(&tokens),
+            backward: AhoCorasick::new(&reversed),
+            values: vocabulary.tokens.iter().map(|(_, d)| *d).collect(),
+        }
+    }
+
+    fn token(&self, m: Match) -> Token {
+        Token { value: self.values[m.pattern], start: m.start, end: m.end }
+    }
+
+    // Earliest token, the longest one when several start there.
+    fn first(&self, line: &[u8]) -> Option<Token> {
+        Some(self.token(self.forward.leftmost_longest(line)?))
+    }
+
+    // Token ending last, the longest one when several end there.
+    fn last(&self, line: &[u8]) -> Option<Token> {
+        let reversed: Vec<u8> = line.iter().rev().copied().collect();
+        let m = self.backward.leftmost_longest(&reversed)?;
+        Some(self.token(Match { pattern: m.pattern, start: line.len() - m.end, end: line.len() - m.start }))
+    }
+
+    fn tokens(&self, line: &str) -> Option<(Token, Token)> {
+        self.byte_tokens(line.as_bytes())
+    }
+
+    // Also fine with lines that aren't valid UTF-8, tokens being matched as bytes.
+    fn byte_tokens(&self, line: &[u8]) -> Option<(Token, Token)> {
+        Some((self.first(line)?, self.last(line)?))
+    }
+}
+
+fn part2_solve(lines: &[&str], extractor: &Extractor) -> Result<Calibration, Overflow> {
+    calibrate(lines, &|line| extractor.tokens(line))
+}
+
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    let vocabulary = Vocabulary::load(options.vocabulary.as_deref().unwrap_or("english"))?;
+    let extractor = Extractor::new(&vocabulary);
+    let lines = parse(&content).map_err(|d| d.to_string())?;
+    if options.explain {
+        print!("{}", explain(&lines, &|line| extractor.tokens(line), std::io::stdout().is_terminal()));
+    }
+    print_calibration(part2_solve(&lines, &extractor))
+}
+
+// EXPLAIN -------------------------------------
+
+const FIRST_STYLE: &str = "\x1b[1;32m";
+const LAST_STYLE: &str = "\x1b[1;34m";
+const BOTH_STYLE: &str = "\x1b[1;35m";
+const RESET: &str = "\x1b[0m";
+
+// One line per input line with the tokens `extract` picks and their value, the
+// same ones `calibrate` sums. With `color` the first token is shown in green,
+// the last one in blue and their overlap, as in `twone`, in magenta.
+fn explain(lines: &[&str], extract: Extract, color: bool) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.iter().copied().enumerate() {
+        if line.trim().is_empty() {
             continue;
         }
+        let shown = match extract(line) {
+            Some(tokens) if color => highlight(line, tokens),
+            _ => line.to_string(),
+        };
+        let details = match extract(line) {
+            Some((first, last)) => format!("first {} {}..{}, last {} {}..{} -> {}",
+                                           &line[first.start..first.end], first.start, first.end,
+                                           &line[last.start..last.end], last.start, last.end,
+                                           calibration_value((first, last))),
+            None => "no digit".to_string(),
+        };
+        out.push_str(&format!("{:>5}: {}  {}\n", i + 1, shown, details));
+    }
+    out
+}
+
+fn highlight(line: &str, (first, last): (Token, Token)) -> String {
+    let mut cuts = vec![0, first.start, first.end, last.start, last.end, line.len()];
+    cuts.sort();
+    cuts.dedup();
+
+    let mut out = String::new();
+    for w in cuts.windows(2) {
+        let (start, end) = (w[0], w[1]);
+        let in_first = first.start <= start && end <= first.end;
+        let in_last = last.start <= start && end <= last.end;
+        let style = match (in_first, in_last) {
+            (true, true) => BOTH_STYLE,
+            (true, false) => FIRST_STYLE,
+            (false, true) => LAST_STYLE,
+            (false, false) => "",
+        };
+        if style.is_empty() {
+            out.push_str(&line[start..end]);
+        } else {
+            out.push_str(&format!("{}{}{}", style, &line[start..end], RESET));
+        }
+    }
+    out
+}
+
+// STREAMING -----------------------------------
+
+// Totals over a stream. Lines without a digit are only counted, their numbers
+// could take as much memory as the input.
+#[derive(Debug, Default, PartialEq)]
+pub struct StreamTotals {
+    pub sum: Answer,
+    pub lines: u64,
+    pub undigited: u64,
+    pub bytes: u64,
+}
+
+impl StreamTotals {
+    fn merge(self, other: StreamTotals) -> Result<StreamTotals, Overflow> {
+        Ok(StreamTotals {
+            sum: answer::add(self.sum, other.sum)?,
+            lines: self.lines + other.lines,
+            undigited: self.undigited + other.undigited,
+            bytes: self.bytes + other.bytes,
+        })
+    }
+}
+
+// ASCII digits and the lead byte of full-width ones, which are EF BC 90..99.
+fn is_digit_candidate(b: u8) -> bool {
+    b.wrapping_sub(b'0') < 10 || b == 0xEF
+}
+
+// Bit i is set when byte i of a block of at most 64 may start a digit. Branch
+// free so the compiler can vectorize it.
+fn digit_candidates(block: &[u8]) -> u64 {
+    block.iter().enumerate().fold(0, |mask, (i, b)| mask | ((is_digit_candidate(*b) as u64) << i))
+}
+
+fn digit_at(bytes: &[u8], i: usize) -> Option<u8> {
+    match bytes[i] {
+        b @ b'0'..=b'9' => Some(b - b'0'),
+        0xEF => match bytes.get(i + 1..i + 3) {
+            Some([0xBC, d @ 0x90..=0x99]) => Some(d - 0x90),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+fn first_digit(bytes: &[u8]) -> Option<u8> {
+    for (n, block) in bytes.chunks(64).enumerate() {
+        let mut mask = digit_candidates(block);
+        while mask != 0 {
+            if let Some(d) = digit_at(bytes, n * 64 + mask.trailing_zeros() as usize) {
+                return Some(d);
+            }
+            mask &= mask - 1;
+        }
+    }
+    None
+}
 
-        let mut rd = None;
-        for i in (0..line.len()).rev() {
-            if let Some(x) = matches_digit(&line[i..]) {
-                rd = Some(x);
-                break;
+fn last_digit(bytes: &[u8]) -> Option<u8> {
+    for (n, block) in bytes.chunks(64).enumerate().rev() {
+        let mut mask = digit_candidates(block);
+        while mask != 0 {
+            let bit = 63 - mask.leading_zeros() as usize;
+            if let Some(d) = digit_at(bytes, n * 64 + bit) {
+                return Some(d);
             }
+            mask &= !(1 << bit);
         }
+    }
+    None
+}
 
-        let ld = ld.unwrap();
-        let rd = rd.unwrap();
-        let mut s = String::with_capacity(2);
-        s.push_str(ld);
-        s.push_str(rd);
-        let v: i32 = s.parse().unwrap();
-        sum += v;
+// Same values as `part1_tokens`, straight from the bytes.
+fn part1_byte_value(line: &[u8]) -> Option<Answer> {
+    Some(first_digit(line)? as Answer * 10 + last_digit(line)? as Answer)
+}
+
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace) || String::from_utf8_lossy(line).trim().is_empty()
+}
 
+fn scan_chunk(chunk: &[u8], value: &(dyn Fn(&[u8]) -> Option<Answer> + Sync)) -> Result<StreamTotals, Overflow> {
+    let mut totals = StreamTotals { bytes: chunk.len() as u64, ..StreamTotals::default() };
+    for line in chunk.split(|b| *b == b'\n') {
+        match value(line) {
+            Some(v) => {
+                totals.sum = answer::add(totals.sum, v)?;
+                totals.lines += 1;
+            }
+            None if is_blank(line) => {},
+            None => {
+                totals.lines += 1;
+                totals.undigited += 1;
+            }
+        }
     }
+    Ok(totals)
+}
 
-    sum
+// Reads `reader` in chunks of about `chunk_size` bytes cut after a newline, one
+// chunk per thread at a time, and scans each batch of chunks in parallel. Memory
+// stays around `chunk_size` times the number of threads, unless a single line is
+// longer than a chunk: its chunk then grows to hold it.
+fn stream_totals(mut reader: impl Read, chunk_size: usize, value: &(dyn Fn(&[u8]) -> Option<Answer> + Sync)) -> Result<StreamTotals, String> {
+    let chunk_size = chunk_size.max(1);
+    let threads = rayon::current_num_threads();
+    let mut totals = StreamTotals::default();
+    let mut carry = Vec::new();
+    let mut done = false;
+    while !done {
+        let mut batch = Vec::with_capacity(threads);
+        while batch.len() < threads && !done {
+            let mut chunk = std::mem::take(&mut carry);
+            loop {
+                // room for a whole chunk, or twice what's held when a line outgrew it
+                let filled = chunk.len();
+                let target = if filled < chunk_size { chunk_size } else { 2 * filled };
+                chunk.resize(target, 0);
+                let read = read_up_to(&mut reader, &mut chunk[filled..]).map_err(|e| e.to_string())?;
+                chunk.truncate(filled + read);
+                if chunk.len() < target {
+                    done = true;
+                    break;
+                }
+                if let Some(cut) = chunk.iter().rposition(|b| *b == b'\n') {
+                    carry = chunk.split_off(cut + 1);
+                    break;
+                }
+            }
+            batch.push(chunk);
+        }
+        let scanned = batch.par_iter()
+            .map(|chunk| scan_chunk(chunk, value))
+            .try_reduce(StreamTotals::default, StreamTotals::merge)
+            .map_err(|e| e.to_string())?;
+        totals = totals.merge(scanned).map_err(|e| e.to_string())?;
+    }
+    Ok(totals)
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(content));
+// Fills `buf` unless the reader ends first, returns how much was read.
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
 }
 
-// --------------------------------------
+// Solves `part` straight from `reader`, without holding the whole input.
+pub fn stream(part: u8, reader: impl Read, chunk_size: usize, options: &Options) -> Result<(), String> {
+    let start = Instant::now();
+    let totals = match part {
+        1 => stream_totals(reader, chunk_size, &part1_byte_value)?,
+        2 => {
+            let vocabulary = Vocabulary::load(options.vocabulary.as_deref().unwrap_or("english"))?;
+            let extractor = Extractor::new(&vocabulary);
+            stream_totals(reader, chunk_size, &|line| extractor.byte_tokens(line).map(calibration_value))?
+        }
+        _ => return Err(format!("day 1 has no part {}", part)),
+    };
+
+    println!("result: {}", totals.sum);
+    if totals.undigited > 0 {
+        eprintln!("warning: {} line(s) without a digit, left out of the sum", totals.undigited);
+    }
+    if options.verbose {
+        let seconds = start.elapsed().as_secs_f64();
+        let mib = totals.bytes as f64 / (1024.0 * 1024.0);
+        eprintln!("scanned {} line(s), {:.1} MiB in {:.3}s ({:.1} MiB/s)", totals.lines, mib, seconds, mib / seconds);
+    }
+    Ok(())
+}
+
+// LINT ----------------------------------------
+
+// Lines are checked against part 2's English vocabulary, spelled out digits
+// being fine there even though part 1 skips them.
+pub fn lint(content: &str) -> Vec<String> {
+    let lines = match parse(content) {
+        Ok(lines) => lines,
+        Err(d) => return vec![d.to_string()],
+    };
+
+    let english = Extractor::new(&Vocabulary::from_words(&ENGLISH));
+    lines.into_iter()
+        .filter(|line| !line.trim().is_empty() && english.tokens(line).is_none())
+        .map(|line| ast::slice_error::<Rule>(content, line, "line has no digit or digit word".to_string()).to_string())
+        .collect()
+}
+
+// TESTS ----------------------------------------
 
 #[cfg(test)]
 mod tests {
     use crate::day1::*;
 
+    fn part1_inner(content: String) -> Result<Answer, Overflow> {
+        calibrate(&parse(&content).unwrap(), &part1_tokens).map(|c| c.sum)
+    }
+
+    fn part2_inner(content: String) -> Result<Answer, Overflow> {
+        part2_solve(&parse(&content).unwrap(), &Extractor::new(&Vocabulary::from_words(&ENGLISH))).map(|c| c.sum)
+    }
+
     #[test]
     fn part1_sample() {
         let input = r#"
@@ -92,7 +499,7 @@ pqr3stu8vwx
 a1b2c3d4e5f
 treb7uchet
 "#;
-        assert_eq!(142, part1_inner(input.into()));
+        assert_eq!(Ok(142), part1_inner(input.into()));
     }
 
     #[test]
@@ -106,6 +513,73 @@ xtwone3four
 zoneight234
 7pqrstsixteen
 "#;
-        assert_eq!(281, part2_inner(input.into()));
+        assert_eq!(Ok(281), part2_inner(input.into()));
+    }
+
+    #[test]
+    fn unicode_lines() {
+        let input = "né1à2\n日本\n３ab७\n\nfünf ９ zwei\n";
+        assert_eq!(Ok(Calibration { sum: 12 + 33 + 99, undigited: vec![2] }), calibrate(&parse(input).unwrap(), &part1_tokens));
+        let english = Extractor::new(&Vocabulary::from_words(&ENGLISH));
+        assert_eq!(Ok(Calibration { sum: 12 + 33 + 99, undigited: vec![2] }), part2_solve(&parse(input).unwrap(), &english));
+        let german = Extractor::new(&Vocabulary::load("german").unwrap());
+        assert_eq!(Ok(Calibration { sum: 12 + 33 + 52, undigited: vec![2] }), part2_solve(&parse(input).unwrap(), &german));
+        assert_eq!(1, lint(input).len());
+        assert!(lint("eightwothree\n7pqrstsixteen\n").is_empty());
+    }
+
+    #[test]
+    fn part2_vocabularies() {
+        let roman = Extractor::new(&Vocabulary::load("roman").unwrap());
+        assert_eq!(Some(84), roman.tokens("xviiixiv").map(calibration_value));
+        let german = Extractor::new(&Vocabulary::load("german").unwrap());
+        assert_eq!(Some(53), german.tokens("fünfzigdrei").map(calibration_value));
+        let custom = Extractor::new(&Vocabulary::parse("# planets\nmars 4\nvenus 2\n").unwrap());
+        assert_eq!(Some(24), custom.tokens("venus, earth, mars").map(calibration_value));
+        assert!(Vocabulary::parse("mars four").is_err());
+        assert!(Vocabulary::load("klingon").is_err());
+    }
+
+    #[test]
+    fn part2_overlapping_words() {
+        let english = Extractor::new(&Vocabulary::from_words(&ENGLISH));
+        assert_eq!(Some(21), english.tokens("twone").map(calibration_value));
+        assert_eq!(Some(83), english.tokens("eightwothree").map(calibration_value));
+        assert_eq!(Some(Token { value: 4, start: 7, end: 11 }), english.last(b"xtwone3four"));
+    }
+
+    #[test]
+    fn stream_matches_calibrate() {
+        let input = "two1nine\neightwothree\nabcone2threexyz\n日本\nxtwone3four\n４nineeightseven2\n\nzoneight234\r\n7pqrstsixteen９x";
+        let english = Extractor::new(&Vocabulary::from_words(&ENGLISH));
+        let part1 = calibrate(&parse(input).unwrap(), &part1_tokens).unwrap();
+        let part2 = part2_solve(&parse(input).unwrap(), &english).unwrap();
+        for chunk_size in [1, 3, 16, 1 << 20] {
+            let totals = stream_totals(input.as_bytes(), chunk_size, &part1_byte_value).unwrap();
+            assert_eq!((part1.sum, part1.undigited.len() as u64, 8, input.len() as u64), (totals.sum, totals.undigited, totals.lines, totals.bytes));
+            let totals = stream_totals(input.as_bytes(), chunk_size, &|line| english.byte_tokens(line).map(calibration_value)).unwrap();
+            assert_eq!((part2.sum, part2.undigited.len() as u64), (totals.sum, totals.undigited));
+        }
+    }
+
+    #[test]
+    fn digit_scan_across_blocks() {
+        let mut line = vec![b'x'; 200];
+        line[70] = b'3';
+        line[130..133].copy_from_slice("８".as_bytes());
+        line[190] = 0xEF;
+        assert_eq!(Some(38), part1_byte_value(&line));
+        assert_eq!(None, part1_byte_value(&line[..70]));
+    }
+
+    #[test]
+    fn explain_lines() {
+        let input = "\ntwone\nab\nx7y\n";
+        let english = Extractor::new(&Vocabulary::from_words(&ENGLISH));
+        let text = explain(&parse(input).unwrap(), &|line| english.tokens(line), false);
+        assert_eq!("    2: twone  first two 0..3, last one 2..5 -> 21\n    3: ab  no digit\n    4: x7y  first 7 1..2, last 7 1..2 -> 77\n", text);
+        let colored = explain(&parse(input).unwrap(), &|line| english.tokens(line), true);
+        assert!(colored.contains("\x1b[1;32mtw\x1b[0m\x1b[1;35mo\x1b[0m\x1b[1;34mne\x1b[0m"));
+        assert!(colored.contains("x\x1b[1;35m7\x1b[0my"));
     }
 }
\ No newline at end of file
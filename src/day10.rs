@@ -1,5 +1,23 @@
-
-#[derive(PartialEq, Debug)]
+use std::fmt;
+use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Checked};
+use crate::ast;
+use crate::cache;
+use crate::diagnostic::Diagnostic;
+use crate::geom::{Direction, Point};
+use crate::grid::Grid;
+use crate::options::Options;
+use crate::search;
+
+// PARSING -------------------------------------
+
+#[derive(Parser)]
+#[grammar = "./day10_grammar.pest"]
+pub struct Sketch;
+
+
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
 enum Tile {
     Ground,
     Start,
@@ -11,313 +29,180 @@ enum Tile {
     SouthEast
 }
 
-enum Rel {
-    Top,
-    Right,
-    Bottom,
-    Left,
-}
-
-#[derive(Debug)]
-struct Map {
-    tiles: Vec<Tile>,
-    width: i32,
-    height: i32,
-    start: usize
-}
-
-impl Map {
-    // pub fn get_valid_neighbors(&self, t: usize) -> Vec<usize> {
-    //     let mut r = Vec::with_capacity(4);
-    //     let (tx, ty) = self.get_pos(t).unwrap();
-    //     if let Some(p) = self.get_tile(tx-1, ty) {
-    //
-    //     }
-    //
-    //     r
-    // }
-
-    pub fn get_pos(&self, t: usize) -> Option<(i32, i32)> {
-        let (rx, ry) = (t as i32 % self.width, t as i32 / self.width);
-        if rx < 0 || rx >= self.width || ry < 0 || ry >= self.height {
-            None
-        } else {
-            Some((rx, ry))
+impl Tile {
+    fn from_char(c: char) -> Tile {
+        match c {
+            '|' => Tile::NorthSouth,
+            '-' => Tile::WestEast,
+            'L' => Tile::NorthEast,
+            'J' => Tile::NorthWest,
+            '7' => Tile::SouthWest,
+            'F' => Tile::SouthEast,
+            '.' => Tile::Ground,
+            'S' => Tile::Start,
+            _ => unreachable!("grammar only accepts tiles")
         }
     }
 
-    pub fn get_index(&self, x: i32, y: i32) -> Option<usize> {
-        if x < 0 || x >= self.width || y < 0 || y >= self.height {
-            None
-        } else {
-            let t = (y * self.width + x) as usize;
-            if t >= self.tiles.len() {
-                None
-            } else {
-                Some(t)
-            }
+    fn to_char(&self) -> char {
+        match self {
+            Tile::Ground => '.',
+            Tile::Start => 'S',
+            Tile::NorthSouth => '|',
+            Tile::WestEast => '-',
+            Tile::NorthEast => 'L',
+            Tile::NorthWest => 'J',
+            Tile::SouthWest => '7',
+            Tile::SouthEast => 'F',
         }
     }
 
-    fn get_rel_pos((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> Option<Rel> {
-        match (x2 - x1, y2 - y1) {
-            (0, -1) => Some(Rel::Top),
-            (1, 0) => Some(Rel::Right),
-            (0, 1) => Some(Rel::Bottom),
-            (-1, 0) => Some(Rel::Left),
-            _ => None
+    // Whether the tile has a pipe end on its `dir` side, the start having all of them.
+    fn opens(&self, dir: Direction) -> bool {
+        match self {
+            Tile::Ground => false,
+            Tile::Start => true,
+            Tile::NorthSouth => matches!(dir, Direction::North | Direction::South),
+            Tile::WestEast => matches!(dir, Direction::West | Direction::East),
+            Tile::NorthEast => matches!(dir, Direction::North | Direction::East),
+            Tile::NorthWest => matches!(dir, Direction::North | Direction::West),
+            Tile::SouthWest => matches!(dir, Direction::South | Direction::West),
+            Tile::SouthEast => matches!(dir, Direction::South | Direction::East),
         }
     }
+}
 
-    pub fn is_connected(&self, (x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> bool {
-        let Some(rel) = Map::get_rel_pos((x1, y1), (x2, y2)) else {
-            return false;
-        };
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Map {
+    tiles: Grid<Tile>,
+    start: Point
+}
 
-        let t1 = self.get_tile(self.get_index(x1, y1).unwrap());
-        let t2 = self.get_tile(self.get_index(x2, y2).unwrap());
-
-        match t1 {
-            Tile::Ground => {}
-            Tile::Start => {}
-            Tile::NorthSouth => {}
-            Tile::WestEast => {}
-            Tile::NorthEast => {}
-            Tile::NorthWest => {}
-            Tile::SouthWest => {}
-            Tile::SouthEast => {}
+impl Map {
+    // Both tiles have to open toward each other.
+    pub fn is_connected(&self, p1: Point, p2: Point) -> bool {
+        let Some(dir) = Direction::from_offset(p2 - p1) else {
+            return false;
         };
 
-        match (rel, t1,t2) {
-            (_, Tile::Ground, _) | (_, _, Tile::Ground) => false,
-
-            (Rel::Top, Tile::NorthSouth, Tile::NorthSouth) => true,
-            (Rel::Top, Tile::NorthSouth, Tile::SouthWest) => true,
-            (Rel::Top, Tile::NorthSouth, Tile::SouthEast) => true,
-            (Rel::Top, Tile::NorthSouth, Tile::Start) => true,
-
-            (Rel::Top, Tile::NorthEast, Tile::NorthSouth) => true,
-            (Rel::Top, Tile::NorthEast, Tile::SouthWest) => true,
-            (Rel::Top, Tile::NorthEast, Tile::SouthEast) => true,
-            (Rel::Top, Tile::NorthEast, Tile::Start) => true,
-
-            (Rel::Top, Tile::NorthWest, Tile::NorthSouth) => true,
-            (Rel::Top, Tile::NorthWest, Tile::SouthWest) => true,
-            (Rel::Top, Tile::NorthWest, Tile::SouthEast) => true,
-            (Rel::Top, Tile::NorthWest, Tile::Start) => true,
-
-            (Rel::Top, Tile::Start, Tile::NorthSouth) => true,
-            (Rel::Top, Tile::Start, Tile::SouthWest) => true,
-            (Rel::Top, Tile::Start, Tile::SouthEast) => true,
-
-            (Rel::Right, Tile::WestEast, Tile::WestEast) => true,
-            (Rel::Right, Tile::WestEast, Tile::NorthWest) => true,
-            (Rel::Right, Tile::WestEast, Tile::SouthWest) => true,
-            (Rel::Right, Tile::WestEast, Tile::Start) => true,
-
-            (Rel::Right, Tile::NorthEast, Tile::WestEast) => true,
-            (Rel::Right, Tile::NorthEast, Tile::NorthWest) => true,
-            (Rel::Right, Tile::NorthEast, Tile::SouthWest) => true,
-            (Rel::Right, Tile::NorthEast, Tile::Start) => true,
-
-            (Rel::Right, Tile::SouthEast, Tile::WestEast) => true,
-            (Rel::Right, Tile::SouthEast, Tile::NorthWest) => true,
-            (Rel::Right, Tile::SouthEast, Tile::SouthWest) => true,
-            (Rel::Right, Tile::SouthEast, Tile::Start) => true,
-
-            (Rel::Right, Tile::Start, Tile::WestEast) => true,
-            (Rel::Right, Tile::Start, Tile::NorthWest) => true,
-            (Rel::Right, Tile::Start, Tile::SouthWest) => true,
-
-            (Rel::Bottom, Tile::NorthSouth, Tile::NorthSouth) => true,
-            (Rel::Bottom, Tile::NorthSouth, Tile::NorthEast) => true,
-            (Rel::Bottom, Tile::NorthSouth, Tile::NorthWest) => true,
-            (Rel::Bottom, Tile::NorthSouth, Tile::Start) => true,
-
-            (Rel::Bottom, Tile::SouthWest, Tile::NorthSouth) => true,
-            (Rel::Bottom, Tile::SouthWest, Tile::NorthEast) => true,
-            (Rel::Bottom, Tile::SouthWest, Tile::NorthWest) => true,
-            (Rel::Bottom, Tile::SouthWest, Tile::Start) => true,
-
-            (Rel::Bottom, Tile::SouthEast, Tile::NorthSouth) => true,
-            (Rel::Bottom, Tile::SouthEast, Tile::NorthEast) => true,
-            (Rel::Bottom, Tile::SouthEast, Tile::NorthWest) => true,
-            (Rel::Bottom, Tile::SouthEast, Tile::Start) => true,
-
-            (Rel::Bottom, Tile::Start, Tile::NorthSouth) => true,
-            (Rel::Bottom, Tile::Start, Tile::NorthEast) => true,
-            (Rel::Bottom, Tile::Start, Tile::NorthWest) => true,
-
-            (Rel::Left, Tile::WestEast, Tile::WestEast) => true,
-            (Rel::Left, Tile::WestEast, Tile::NorthEast) => true,
-            (Rel::Left, Tile::WestEast, Tile::SouthEast) => true,
-            (Rel::Left, Tile::WestEast, Tile::Start) => true,
-
-            (Rel::Left, Tile::NorthWest, Tile::WestEast) => true,
-            (Rel::Left, Tile::NorthWest, Tile::NorthEast) => true,
-            (Rel::Left, Tile::NorthWest, Tile::SouthEast) => true,
-            (Rel::Left, Tile::NorthWest, Tile::Start) => true,
-
-            (Rel::Left, Tile::SouthWest, Tile::WestEast) => true,
-            (Rel::Left, Tile::SouthWest, Tile::NorthEast) => true,
-            (Rel::Left, Tile::SouthWest, Tile::SouthEast) => true,
-            (Rel::Left, Tile::SouthWest, Tile::Start) => true,
-
-            (Rel::Left, Tile::Start, Tile::WestEast) => true,
-            (Rel::Left, Tile::Start, Tile::NorthEast) => true,
-            (Rel::Left, Tile::Start, Tile::SouthEast) => true,
-
-            _ => false,
-        }
+        let t1 = self.tiles.get(p1).unwrap();
+        let t2 = self.tiles.get(p2).unwrap();
+        t1.opens(dir) && t2.opens(dir.back()) && !(*t1 == Tile::Start && *t2 == Tile::Start)
     }
+}
 
-    pub fn get_tile(&self, t: usize) -> &Tile {
-        self.tiles.get(t).unwrap()
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
     }
 }
 
-fn parse(content: &str) -> Map {
-    let lines_separator = if content.contains("\r\n") {
-        "\r\n"
-    } else {
-        "\n"
-    };
-
-    let lines = content
-        .trim()
-        .split(lines_separator);
-
-    let mut tiles: Vec<Tile> = Vec::new();
-    let mut height = 0i32;
-    for line in lines {
-        height += 1;
-        for c in line.chars() {
-            let tile = match c {
-               '|' => Tile::NorthSouth,
-               '-' => Tile::WestEast,
-               'L' => Tile::NorthEast,
-               'J' => Tile::NorthWest,
-               '7' => Tile::SouthWest,
-               'F' => Tile::SouthEast,
-               '.' => Tile::Ground,
-               'S' => Tile::Start,
-               _ => panic!("Couldn't parse Tile '{}'", c)
-            };
-            tiles.push(tile);
-        }
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tiles)
     }
-    let width = tiles.len() as i32 / height;
-    let start = tiles.iter().position(|t| *t == Tile::Start).expect("Map has no start!");
-    Map { tiles, width, height, start }
 }
 
+fn parse(content: &str) -> Result<Map, Diagnostic> {
+    let tiles = Grid::parse::<Sketch, _>(Rule::map, Rule::row, content, Tile::from_char)?;
+    let start = tiles.find(|t| *t == Tile::Start)
+        .ok_or_else(|| ast::slice_error::<Rule>(content, &content[..0], "map has no start tile 'S'".to_string()))?;
+    Ok(Map { tiles, start })
+}
 
-fn is_same_as_last(last: &Option<usize>, val: usize) -> bool {
-    match last {
-        None => false,
-        Some(x) => *x == val
-    }
+pub fn dump(content: &str, _options: &Options, json: bool) -> Result<String, String> {
+    let map = parse(content).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&map, json))
 }
 
-fn build_loop(map: &Map) -> Vec<usize> {
-    let mut last = None;
-    let mut current = map.start;
-    let mut nodes = Vec::new();
-    loop {
-        if nodes.len() >= 1 && current == map.start {
-            break;
-        }
-        nodes.push(current);
 
-        let (x, y) = map.get_pos(current).unwrap();
-        // println!("({}, {})", x, y);
-        // assert!(count < 100);
+fn connections(map: &Map, p: Point) -> impl Iterator<Item = Point> + '_ {
+    map.tiles.neighbors4(p).filter(move |n| map.is_connected(p, *n))
+}
 
-        if let Some(top) = map.get_index(x, y - 1) {
-            if map.is_connected((x, y), (x, y - 1)) && !is_same_as_last(&last, top) {
-                last = Some(current);
-                current = top;
-                continue;
-            }
-        }
-        if let Some(right) = map.get_index(x + 1, y) {
-            if map.is_connected((x, y), (x + 1, y)) && !is_same_as_last(&last, right) {
-                last = Some(current);
-                current = right;
-                continue;
-            }
-        }
-        if let Some(bottom) = map.get_index(x, y + 1) {
-            if map.is_connected((x, y), (x, y + 1)) && !is_same_as_last(&last, bottom) {
-                last = Some(current);
-                current = bottom;
-                continue;
-            }
-        }
-        if let Some(left) = map.get_index(x - 1, y){
-            if map.is_connected((x, y), (x - 1, y)) && !is_same_as_last(&last, left)  {
-                last = Some(current);
-                current = left;
-                continue;
+// Follows the pipes from the start, leaving through each of its connections in
+// turn until one walk comes back to it. A state is the current tile and the one
+// we came from, None once the walk hits a dead end. A walk back at the start
+// leaves it the same way again, so a loop repeats from the very first state and
+// a dead end only from None on.
+fn build_loop(map: &Map) -> Option<Vec<Point>> {
+    connections(map, map.start).find_map(|first| {
+        let next = |&(current, last): &(Point, Point)| {
+            if current == map.start {
+                return Some((first, current));
             }
+            connections(map, current).find(|n| *n != last).map(|n| (n, current))
+        };
+        let cycle = search::brent(Some((first, map.start)), |s| s.as_ref().and_then(next));
+        if cycle.start != 0 {
+            return None;
         }
+        let nodes = std::iter::successors(Some((first, map.start)), next)
+            .take(cycle.length)
+            .map(|(p, _)| p)
+            .collect();
+        Some(nodes)
+    })
+}
 
+// The map with its loop, turned so the inside is on the right hand side.
+#[derive(Debug, Serialize, Deserialize)]
+struct Prepared {
+    map: Map,
+    loop_nodes: Vec<Point>
+}
 
-        panic!("main dead loop");
-    }
+fn prepare(content: &str) -> Result<Prepared, Diagnostic> {
+    let map = parse(content)?;
+    let Some(loop_nodes) = build_loop(&map) else {
+        let at = content.find('S').unwrap();
+        return Err(ast::slice_error::<Rule>(content, &content[at..at + 1], "start tile is not on a loop".to_string()));
+    };
+    let loop_nodes = spin_loop(&map, loop_nodes);
+    Ok(Prepared { map, loop_nodes })
+}
 
-    nodes
+// From the cache when `--cache` is given.
+fn load(content: &str, options: &Options) -> Result<Prepared, String> {
+    cache::load_or_build(options.cache.as_deref(), "day10-loop", content, || prepare(content))
+        .map_err(|d| d.to_string())
 }
 
 // PART 1 --------------------------------------
 
-fn part1_inner(content: &str) -> i64 {
-    let map = parse(content);
-    let nodes = build_loop(&map);
-    nodes.len() as i64 / 2
+// The farthest tile is halfway round the loop.
+fn part1_solve(prepared: &Prepared) -> Checked {
+    answer::of(prepared.loop_nodes.len() / 2)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    println!("result: {}", part1_solve(&load(&content, options)?).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
-fn get_right_hand_vec((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> (i32, i32) {
-    let dv = (x2 - x1, y2 - y1);
-    match dv {
-        (-1, 0) => (0, -1),
-        (1, 0) => (0, 1),
-        (0, -1) => (1, 0),
-        (0, 1) => (-1, 0),
-        _ => panic!("impossible vec {:?}", dv)
-    }
+fn get_right_hand_vec(p1: Point, p2: Point) -> Point {
+    let dir = Direction::from_offset(p2 - p1).unwrap_or_else(|| panic!("impossible vec {}", p2 - p1));
+    dir.turn_right().offset()
 }
 
-fn spin_loop(map: &Map, mut loop_nodes: Vec<usize>) -> Vec<usize> {
+fn spin_loop(map: &Map, mut loop_nodes: Vec<Point>) -> Vec<Point> {
     let mut need_reverse = false;
-    for (i, index) in loop_nodes.iter().enumerate() {
-        let current_pos = map.get_pos(*index).unwrap();
-        let next_pos = map.get_pos(
-            *loop_nodes.get(i+1).unwrap_or(&loop_nodes[0])
-        ).unwrap();
-        let rhs = get_right_hand_vec(current_pos, next_pos);
+    for (i, current_pos) in loop_nodes.iter().enumerate() {
+        let next_pos = *loop_nodes.get(i+1).unwrap_or(&loop_nodes[0]);
+        let rhs = get_right_hand_vec(*current_pos, next_pos);
         let mut dt = 1;
         loop {
-            let side_node = (current_pos.0 + (rhs.0 * dt), current_pos.1 + (rhs.1 * dt));
-            let side_node_index = map.get_index(side_node.0, side_node.1);
-            match side_node_index {
-                None => {
-                    need_reverse = true;
-                    break;
-                }
-                Some(index) if loop_nodes.contains(&index) => {
-                    break;
-                }
-                _ => {
-                    dt += 1;
-                }
-            };
+            let side_node = *current_pos + rhs * dt;
+            if !map.tiles.contains(side_node) {
+                need_reverse = true;
+                break;
+            }
+            if loop_nodes.contains(&side_node) {
+                break;
+            }
+            dt += 1;
         }
         if need_reverse {
             break;
@@ -331,22 +216,15 @@ fn spin_loop(map: &Map, mut loop_nodes: Vec<usize>) -> Vec<usize> {
 }
 
 
-pub fn part2_inner(content: &str) -> i64 {
-    let map = parse(content);
-    let loop_nodes = spin_loop(&map, build_loop(&map));
+fn part2_solve(prepared: &Prepared) -> Checked {
+    let Prepared { map, loop_nodes } = prepared;
     let mut inner_nodes = Vec::with_capacity(loop_nodes.len());
-    for (i, index) in loop_nodes.iter().enumerate() {
-        let current_pos = map.get_pos(*index).unwrap();
-        let next_pos = map.get_pos(
-            *loop_nodes.get(i+1).unwrap_or(&loop_nodes[0])
-        ).unwrap();
-        let rhs = get_right_hand_vec(current_pos, next_pos);
-        let side_node = (current_pos.0 + rhs.0, current_pos.1 + rhs.1);
-        let side_node_index = map.get_index(side_node.0, side_node.1);
-        if let Some(side_node_index) = side_node_index {
-            if !loop_nodes.contains(&side_node_index) && !inner_nodes.contains(&side_node_index) {
-                inner_nodes.push(side_node_index);
-            }
+    for (i, current_pos) in loop_nodes.iter().enumerate() {
+        let next_pos = *loop_nodes.get(i+1).unwrap_or(&loop_nodes[0]);
+        let rhs = get_right_hand_vec(*current_pos, next_pos);
+        let side_node = *current_pos + rhs;
+        if map.tiles.contains(side_node) && !loop_nodes.contains(&side_node) && !inner_nodes.contains(&side_node) {
+            inner_nodes.push(side_node);
         }
 
     }
@@ -354,71 +232,77 @@ pub fn part2_inner(content: &str) -> i64 {
 
     for i in 0..inner_nodes.len() {
         let inner_node = inner_nodes[i];
-        let (x, y) = map.get_pos(inner_node).unwrap();
-
-        if let Some(left) = map.get_index(x - 1, y) {
-            if !loop_nodes.contains(&left) && !inner_nodes.contains(&left) {
-                inner_nodes.push(left);
+        for next in map.tiles.neighbors4(inner_node) {
+            if !loop_nodes.contains(&next) && !inner_nodes.contains(&next) {
+                inner_nodes.push(next);
             }
         }
-        if let Some(right) = map.get_index(x + 1, y) {
-            if !loop_nodes.contains(&right) && !inner_nodes.contains(&right) {
-                inner_nodes.push(right);
-            }
-        }
-        if let Some(top) = map.get_index(x, y-1) {
-            if !loop_nodes.contains(&top) && !inner_nodes.contains(&top) {
-                inner_nodes.push(top);
-            }
-        }
-        if let Some(bottom) = map.get_index(x, y+1) {
-            if !loop_nodes.contains(&bottom) && !inner_nodes.contains(&bottom) {
-                inner_nodes.push(bottom);
-            }
-        }
-
     }
 
     // print_map(&map, &inner_nodes, &Vec::new(), &loop_nodes);
 
-    inner_nodes.len() as i64
+    answer::of(inner_nodes.len())
 }
 
-fn _print_map(map: &Map, in_nodes: &Vec<usize>, out_nodes: &Vec<usize>, loop_nodes: &Vec<usize>) {
-    for y in 0..map.height {
-        for x in 0..map.width {
-            let index = map.get_index(x, y).unwrap();
-            let tile = map.get_tile(index);
-            let loop_index = loop_nodes.iter().position(|e| *e == index);
-            match loop_index {
-                None => {
-                    let s = match tile {
-                        _ if in_nodes.contains(&index) => "I",
-                        _ if out_nodes.contains(&index) => "O",
-                        Tile::Ground => ".",
-                        Tile::Start => "S",
-                        Tile::NorthSouth => "|",
-                        Tile::WestEast => "-",
-                        Tile::NorthEast => "L",
-                        Tile::NorthWest => "J",
-                        Tile::SouthWest => "7",
-                        Tile::SouthEast => "F",
-                    };
-                    print!("{} ", s);
-                }
-                Some(i) => {
-                    print!("{} ", i);
-                }
+fn _print_map(map: &Map, in_nodes: &[Point], out_nodes: &[Point], loop_nodes: &[Point]) {
+    for (pos, tile) in map.tiles.iter() {
+        let loop_index = loop_nodes.iter().position(|e| *e == pos);
+        match loop_index {
+            None => {
+                let s = match tile {
+                    _ if in_nodes.contains(&pos) => 'I',
+                    _ if out_nodes.contains(&pos) => 'O',
+                    t => t.to_char(),
+                };
+                print!("{} ", s);
             }
-
+            Some(i) => {
+                print!("{} ", i);
+            }
+        }
+        if pos.x as usize == map.tiles.width() - 1 {
+            println!();
         }
-        println!();
     }
 }
 
 // BAD ANSWER - TOO LOW: 286
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    println!("result: {}", part2_solve(&load(&content, options)?).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    let rows: Vec<&str> = match ast::parse_children::<Sketch, _, _>(Rule::map, Rule::row, content) {
+        Ok(rows) => rows,
+        Err(d) => return vec![d.to_string()],
+    };
+
+    let starts: Vec<&str> = rows.iter()
+        .flat_map(|row| row.match_indices('S').map(|(_, s)| s))
+        .collect();
+    match starts.len() {
+        0 => return vec!["map has no start tile 'S'".to_string()],
+        1 => {},
+        n => return starts.iter().skip(1)
+            .map(|s| ast::slice_error::<Rule>(content, s, format!("map has {} start tiles, expected exactly one", n)).to_string())
+            .collect()
+    }
+
+    let map = match parse(content) {
+        Ok(map) => map,
+        Err(d) => return vec![d.to_string()],
+    };
+    let connected = map.tiles.neighbors4(map.start)
+        .filter(|n| map.is_connected(map.start, *n))
+        .count();
+    if connected != 2 {
+        return vec![format!("start tile at {} connects to {} pipes, a loop needs exactly 2", map.start, connected)];
+    }
+
+    Vec::new()
 }
 
 // TESTS --------------------------------------
@@ -428,7 +312,15 @@ pub fn part2(content: String) {
 mod tests {
     use super::*;
 
-    static SAMPLE_1: &'static str = r#"
+    fn part1_inner(content: &str) -> Checked {
+        part1_solve(&prepare(content).unwrap())
+    }
+
+    fn part2_inner(content: &str) -> Checked {
+        part2_solve(&prepare(content).unwrap())
+    }
+
+    static SAMPLE_1: &str = r#"
 .....
 .S-7.
 .|.|.
@@ -436,7 +328,7 @@ mod tests {
 .....
 "#;
 
-    static SAMPLE_2: &'static str = r#"
+    static SAMPLE_2: &str = r#"
 -L|F7
 7S-7|
 L|7||
@@ -444,7 +336,7 @@ L|7||
 L|-JF
 "#;
 
-    static SAMPLE_3: &'static str = r#"
+    static SAMPLE_3: &str = r#"
 ..F7.
 .FJ|.
 SJ.L7
@@ -454,20 +346,33 @@ LJ...
 
     #[test]
     fn part1_sample1() {
-        assert_eq!(4, part1_inner(SAMPLE_1));
+        assert_eq!(Ok(4), part1_inner(SAMPLE_1));
     }
 
     #[test]
     fn part1_sample2() {
-        assert_eq!(4, part1_inner(SAMPLE_2));
+        assert_eq!(Ok(4), part1_inner(SAMPLE_2));
     }
 
     #[test]
     fn part1_sample3() {
-        assert_eq!(8, part1_inner(SAMPLE_3));
+        assert_eq!(Ok(8), part1_inner(SAMPLE_3));
     }
 
-    static SAMPLE_4: &'static str = r#"
+    #[test]
+    fn part1_ignores_dead_ends() {
+        let map = "-----S-7.\n.....|.|.\n.....L-J.\n";
+        assert_eq!(Ok(4), part1_inner(map));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("map has no start tile 'S'", parse(".F7\n.LJ\n").unwrap_err().message);
+        assert_eq!((2, 2), parse("S-7\n|x|\n").map(|_| ()).map_err(|d| (d.line, d.col)).unwrap_err());
+        assert_eq!((2, 2), prepare("F-7\n|S|\nL-J\n").map(|_| ()).map_err(|d| (d.line, d.col)).unwrap_err());
+    }
+
+    static SAMPLE_4: &str = r#"
 ...........
 .S-------7.
 .|F-----7|.
@@ -479,7 +384,7 @@ LJ...
 ...........
 "#;
 
-    static SAMPLE_5: &'static str = r#"
+    static SAMPLE_5: &str = r#"
 .F----7F7F7F7F-7....
 .|F--7||||||||FJ....
 .||.FJ||||||||L7....
@@ -492,7 +397,7 @@ L--J.L7...LJS7F-7L7.
 ....L---J.LJ.LJLJ...
 "#;
 
-    static SAMPLE_6: &'static str = r#"
+    static SAMPLE_6: &str = r#"
 FF7FSF7F7F7F7F7F---7
 L|LJ||||||||||||F--J
 FL-7LJLJ||||||LJL-77
@@ -506,17 +411,24 @@ L7JLJL-JLJLJL--JLJ.L
 "#;
     #[test]
     fn part2_sample4() {
-        assert_eq!(4, part2_inner(SAMPLE_4));
+        assert_eq!(Ok(4), part2_inner(SAMPLE_4));
     }
 
     #[test]
     fn part2_sample5() {
-        assert_eq!(8, part2_inner(SAMPLE_5));
+        assert_eq!(Ok(8), part2_inner(SAMPLE_5));
     }
 
     #[test]
     fn part2_sample6() {
-        assert_eq!(10, part2_inner(SAMPLE_6));
+        assert_eq!(Ok(10), part2_inner(SAMPLE_6));
     }
 
+    #[test]
+    fn display_round_trip() {
+        let map = parse(SAMPLE_2).unwrap();
+        let text = map.to_string();
+        assert_eq!(SAMPLE_2.trim(), text);
+        assert_eq!(map, parse(&text).unwrap());
+    }
 }
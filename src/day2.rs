@@ -1,156 +1,583 @@
-use std::collections::HashMap;
-use pest::iterators::Pair;
-use pest::Parser;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
 use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Answer, Checked};
+use crate::ast;
+use crate::day2_query;
+use crate::diagnostic::Diagnostics;
+use crate::options::{Absent, Options, UnknownColor};
+use crate::from_pair;
 
 // PARSING ---------------------------------------
 
-#[derive(Debug)]
-struct Game<'a> {
-    id: i32,
-    sets: Vec<Set<'a>>
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Game<'a> {
+    pub id: i32,
+    pub sets: Vec<Set<'a>>
 }
-#[derive(Debug)]
-struct Set<'a> {
-    cubes: Vec<Cubes<'a>>
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Set<'a> {
+    pub cubes: Vec<Cubes<'a>>
 }
-#[derive(Debug)]
-struct Cubes<'a> {
-    color: &'a str,
-    count: i32,
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cubes<'a> {
+    pub color: Cow<'a, str>,
+    pub count: i32,
 }
 
 #[derive(Parser)]
 #[grammar = "./day2_grammar.pest"]
 pub struct Games;
 
-fn parse_game(game: Pair<Rule>) -> Game {
-    let mut id: i32 = 0;
-    let mut sets: Vec<Set> = Vec::new();
-    for r in game.into_inner() {
-        match r.as_rule() {
-            Rule::game_id => id = r.as_str().parse().unwrap(),
-            Rule::sets => sets = r.into_inner().map(|set| parse_set(set)).collect(),
-            _ => panic!("{:?}", r)
-        };
+from_pair!(Rule => Game<'i> { id: one(game_id), sets: many(set) });
+from_pair!(Rule => Set<'i> { cubes: many(cubes) });
+from_pair!(Rule => Cubes<'i> { color: one(cubes_type), count: one(cubes_count) });
+
+impl fmt::Display for Game<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}: ", self.id)?;
+        for (i, set) in self.sets.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", set)?;
+        }
+        Ok(())
     }
+}
 
-    Game { id, sets }
+impl fmt::Display for Set<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, cubes) in self.cubes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", cubes)?;
+        }
+        Ok(())
+    }
 }
 
-fn parse_set(set: Pair<Rule>) -> Set {
-    let cubes = set.into_inner()
-        .filter(|r| r.as_rule() == Rule::cubes)
-        .map(|r| parse_cubes(r))
+impl fmt::Display for Cubes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.count, self.color)
+    }
+}
+
+fn parse_input(content: &str, lenient: bool) -> Result<Vec<Game<'_>>, Diagnostics> {
+    ast::parse_records::<Games, _, _>(Rule::record, content).accept(lenient)
+}
+
+pub fn dump(content: &str, options: &Options, json: bool) -> Result<String, String> {
+    let games = parse_input(content, options.lenient).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&games, json))
+}
+
+// COLOR MAXIMA --------------------------------
+
+// Highest count of each color over a game's sets: what the bag must hold at
+// least. Colors are numbered once for all games, so checking a bag is a walk
+// over small vectors.
+#[derive(Debug, PartialEq)]
+struct Maxima<'a> {
+    colors: Vec<&'a str>,
+    // game id, then the maximum of each color, None when the game never shows it
+    games: Vec<(i32, Vec<Option<i32>>)>
+}
+
+fn color_maxima<'a>(games: &'a [Game]) -> Maxima<'a> {
+    let mut index: BTreeMap<&str, usize> = BTreeMap::new();
+    for cubes in games.iter().flat_map(|g| &g.sets).flat_map(|s| &s.cubes) {
+        let next = index.len();
+        index.entry(cubes.color.as_ref()).or_insert(next);
+    }
+
+    let mut colors = vec![""; index.len()];
+    for (color, i) in &index {
+        colors[*i] = color;
+    }
+    let games = games.iter()
+        .map(|game| {
+            let mut maxima = vec![None; colors.len()];
+            for cubes in game.sets.iter().flat_map(|s| &s.cubes) {
+                let max = &mut maxima[index[cubes.color.as_ref()]];
+                *max = Some(max.map_or(cubes.count, |m: i32| m.max(cubes.count)));
+            }
+            (game.id, maxima)
+        })
         .collect();
+    Maxima { colors, games }
+}
+
+// PART 1 --------------------------------------
+
+const DEFAULT_BAG: &str = "red=12,green=13,blue=14";
 
-    Set { cubes }
+// How many cubes of each color the bag holds, written like `DEFAULT_BAG`.
+#[derive(Debug, Clone, PartialEq)]
+struct Bag {
+    counts: BTreeMap<String, i32>
 }
 
-fn parse_cubes(cube: Pair<Rule>) -> Cubes {
-    let mut count = 0;
-    let mut color = "";
+impl FromStr for Bag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut counts = BTreeMap::new();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (color, count) = pair.split_once('=')
+                .ok_or_else(|| format!("expected 'color=count', got '{}'", pair))?;
+            let count = count.trim().parse::<i32>()
+                .map_err(|e| format!("invalid count in '{}': {}", pair, e))?;
+            if counts.insert(color.trim().to_string(), count).is_some() {
+                return Err(format!("color '{}' is given twice", color.trim()));
+            }
+        }
+        Ok(Bag { counts })
+    }
+}
 
-    for r in cube.into_inner() {
-        match r.as_rule() {
-            Rule::cubes_count => count = r.as_str().parse().unwrap(),
-            Rule::cubes_type => color = r.as_str(),
-            _ => {}
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (color, count)) in self.counts.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", color, count)?;
         }
+        Ok(())
     }
+}
 
-    Cubes { color, count }
+// Lines of `color=count,...`, `#` starting a comment.
+fn parse_bags(text: &str) -> Result<Vec<Bag>, String> {
+    text.lines().enumerate()
+        .map(|(i, line)| (i, line.split('#').next().unwrap().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| line.parse().map_err(|e| format!("line {}: {}", i + 1, e)))
+        .collect()
 }
 
-fn parse_input(content: &str) -> Vec<Game> {
-    let games_file = Games::parse(Rule::games, &content)
-        .expect("couldn't parse content!")
-        .next().unwrap();
+// Ids of the games whose every set could be drawn from the bag.
+fn feasible_ids(maxima: &Maxima, bag: &Bag, unknown: UnknownColor) -> Result<Vec<i32>, String> {
+    let limits: Vec<Option<i32>> = maxima.colors.iter().map(|c| bag.counts.get(*c).copied()).collect();
+    let mut ids = Vec::new();
+    'games: for (id, game) in &maxima.games {
+        for (color, (max, limit)) in game.iter().zip(&limits).enumerate() {
+            match (max, limit, unknown) {
+                (None, _, _) | (Some(_), None, UnknownColor::Ignore) => {},
+                (Some(max), Some(limit), _) if max <= limit => {},
+                (Some(_), Some(_), _) | (Some(_), None, UnknownColor::Reject) => continue 'games,
+                (Some(_), None, UnknownColor::Error) => return Err(format!("game {} has {} cubes, which the bag doesn't list", id, maxima.colors[color])),
+            }
+        }
+        ids.push(*id);
+    }
+    Ok(ids)
+}
 
-    let games: Vec<Game> = games_file.into_inner()
-        .filter(|r| r.as_rule() == Rule::game)
-        .map(|r| parse_game(r))
-        .collect();
+// Feasible ids for each bag, the maxima being computed once for all of them.
+fn feasible_ids_batch(games: &[Game], bags: &[Bag], unknown: UnknownColor) -> Result<Vec<Vec<i32>>, String> {
+    let maxima = color_maxima(games);
+    bags.iter().map(|bag| feasible_ids(&maxima, bag, unknown)).collect()
+}
 
-    games
+fn part1_solve(ids: &[i32]) -> Checked {
+    answer::sum(ids.iter().copied())
 }
 
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    let games = parse_input(&content, options.lenient).map_err(|d| d.to_string())?;
+    let bags = match &options.bags {
+        Some(path) => fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|text| parse_bags(&text))
+            .map_err(|e| format!("{}: {e}", path.display()))?,
+        None => vec![options.bag.as_deref().unwrap_or(DEFAULT_BAG).parse().map_err(|e| format!("--bag: {e}"))?],
+    };
+    let feasible = feasible_ids_batch(&games, &bags, options.unknown_color)?;
+    for (bag, ids) in bags.iter().zip(&feasible) {
+        let listed: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        println!("feasible games with {}: {}", bag, listed.join(", "));
+        println!("result: {}", part1_solve(ids).map_err(|e| e.to_string())?);
+    }
+    Ok(())
+}
 
-// PART 1 --------------------------------------
 
-pub fn part1_inner(content: &str) -> i32 {
-    let games = parse_input(&content);
-    // let contraints: Vec<Cubes> = vec![
-    //     Cubes {color: "red", count: 12},
-    //     Cubes {color: "green", count: 13},
-    //     Cubes {color: "blue", count: 14},
-    // ];
+// PART 2 --------------------------------------
 
-    let mut constraints = HashMap::with_capacity(3);
-    constraints.insert("red", 12);
-    constraints.insert("green", 13);
-    constraints.insert("blue", 14);
+// Turns a game's minimum bag into its power. All but `Weighted` look at every
+// color seen in the input, `Weighted` at the colors it declares.
+#[derive(Debug, Clone, PartialEq)]
+enum Aggregation {
+    Product,
+    Sum,
+    Max,
+    Weighted(BTreeMap<String, i32>),
+}
 
-    let mut sum = 0;
-    for game in &games {
-        let mut ok = true;
-        for set in &game.sets {
-            for cubes in &set.cubes {
-                if cubes.count > *constraints.get(cubes.color).unwrap() {
-                    ok = false;
-                    break;
-                }
-            }
-            if ok == false {
-                break;
+impl FromStr for Aggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(Aggregation::Product),
+            "sum" => Ok(Aggregation::Sum),
+            "max" => Ok(Aggregation::Max),
+            _ => match s.strip_prefix("weighted:") {
+                Some(weights) => Ok(Aggregation::Weighted(weights.parse::<Bag>()?.counts)),
+                None => Err(format!("unknown strategy '{}', expected product, sum, max or weighted:color=weight,...", s)),
             }
         }
-        if ok {
-            sum += game.id;
+    }
+}
+
+impl Aggregation {
+    fn power(&self, colors: &[&str], maxima: &[Option<i32>], absent: Absent) -> Checked {
+        let absent = match absent {
+            Absent::Zero => 0,
+            Absent::One => 1,
+        };
+        let count = |color: &str| colors.iter().position(|c| *c == color)
+            .and_then(|i| maxima[i])
+            .unwrap_or(absent) as Answer;
+        let counts = colors.iter().map(|c| count(c));
+        match self {
+            Aggregation::Product => answer::product(counts),
+            Aggregation::Sum => answer::sum(counts),
+            Aggregation::Max => Ok(counts.max().unwrap_or(0)),
+            Aggregation::Weighted(weights) => weights.iter()
+                .try_fold(0, |acc, (c, w)| answer::add(acc, answer::mul(*w as Answer, count(c))?)),
         }
     }
+}
 
-    sum
+// The bag each game needs at least, colors it never shows left out.
+fn minimum_bag(colors: &[&str], maxima: &[Option<i32>]) -> Bag {
+    let counts = colors.iter().zip(maxima)
+        .filter_map(|(c, m)| Some((c.to_string(), (*m)?)))
+        .collect();
+    Bag { counts }
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+fn part2_solve(games: &[Game], aggregation: &Aggregation, absent: Absent) -> Checked {
+    let maxima = color_maxima(games);
+    let mut sum: Answer = 0;
+    for (_, maxs) in &maxima.games {
+        let power = aggregation.power(&maxima.colors, maxs, absent)?;
+        sum = answer::add(sum, power)?;
+    }
+
+    Ok(sum)
 }
 
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    let games = parse_input(&content, options.lenient).map_err(|d| d.to_string())?;
+    let aggregation: Aggregation = options.power.as_deref().unwrap_or("product").parse().map_err(|e| format!("--power: {e}"))?;
+    if options.explain {
+        let maxima = color_maxima(&games);
+        for (id, maxs) in &maxima.games {
+            let power = aggregation.power(&maxima.colors, maxs, options.absent).map_err(|e| e.to_string())?;
+            println!("Game {}: minimum bag {}, power {}", id, minimum_bag(&maxima.colors, maxs), power);
+        }
+    }
+    println!("result: {}", part2_solve(&games, &aggregation, options.absent).map_err(|e| e.to_string())?);
+    Ok(())
+}
 
-// PART 2 --------------------------------------
+// INFERENCE -----------------------------------
 
+// Each set is taken as drawn without replacement from the same bag, the cubes
+// going back in between sets. A set with x_i cubes of each color, n in all, has
+// probability prod C(N_i, x_i) / C(N, n) for a bag of N_i of each color, N in all.
 
-pub fn part2_inner(content: &str) -> i32 {
-    let games = parse_input(&content);
-    let mut sum = 0;
-    for game in &games {
-        let mut maxs: HashMap<&str, i32> = HashMap::with_capacity(3);
+// As the bag grows with its colors in fixed proportions p_i, that probability
+// tends to the one of drawing with replacement, n! / prod x_i! * prod p_i^x_i.
+// Sets that vary more than such draws never settle on a bag: every bag is beaten
+// by a bigger one, and only the proportions are reported.
+
+// Largest count of a color the search goes to.
+const MAX_COUNT: i64 = i32::MAX as i64;
 
-        for set in &game.sets {
+// Most bags the ranking scores before it gives up on finding the rest of the top.
+const MAX_SCORED: usize = 100_000;
+
+// The cubes of every set by color index, the colors of `Maxima`.
+fn observed_sets(games: &[Game], colors: &[&str]) -> Vec<Vec<i64>> {
+    games.iter().flat_map(|g| &g.sets)
+        .map(|set| {
+            let mut counts = vec![0; colors.len()];
             for cubes in &set.cubes {
-                match maxs.get(cubes.color) {
-                    Some(last) if *last < cubes.count => { maxs.insert(cubes.color, cubes.count); },
-                    None => { maxs.insert(cubes.color, cubes.count); },
-                    _ => {}
-                };
+                let i = colors.iter().position(|c| *c == cubes.color).unwrap();
+                counts[i] += cubes.count as i64;
             }
+            counts
+        })
+        .collect()
+}
+
+// ln C(n, k) as a product of min(k, n - k) ratios, so it costs nothing in n.
+fn ln_choose(n: i64, k: i64) -> f64 {
+    if k < 0 || k > n {
+        return f64::NEG_INFINITY;
+    }
+    (0..k.min(n - k)).map(|j| ((n - j) as f64 / (j + 1) as f64).ln()).sum()
+}
+
+// Log-likelihood of the observed sets for `bag`, minus infinity when it can't
+// have produced them.
+fn log_likelihood(games: &[Game], bag: &Bag) -> f64 {
+    let maxima = color_maxima(games);
+    if maxima.colors.iter().any(|c| !bag.counts.contains_key(*c)) {
+        return f64::NEG_INFINITY;
+    }
+    if bag.counts.values().any(|c| *c < 0) {
+        return f64::NEG_INFINITY;
+    }
+    let counts: Vec<i64> = maxima.colors.iter().map(|c| bag.counts[*c] as i64).collect();
+    let total: i64 = bag.counts.values().map(|c| *c as i64).sum();
+    let mut score = 0.0;
+    for set in observed_sets(games, &maxima.colors) {
+        let ways: f64 = set.iter().zip(&counts).map(|(x, n)| ln_choose(*n, *x)).sum();
+        if ways == f64::NEG_INFINITY {
+            return ways;
         }
+        score += ways - ln_choose(total, set.iter().sum());
+    }
+    score
+}
+
+// Fewest cubes of each color that make every game possible.
+fn lower_bounds(maxima: &Maxima) -> Vec<i64> {
+    (0..maxima.colors.len())
+        .map(|i| maxima.games.iter().filter_map(|(_, m)| m[i]).max().unwrap_or(0) as i64)
+        .collect()
+}
+
+// `log_likelihood` of bags holding only the observed colors, which splits into a
+// term per color and one of the total.
+struct Scorer {
+    sets: Vec<Vec<i64>>,
+    sizes: Vec<i64>,
+}
+
+impl Scorer {
+    fn new(sets: Vec<Vec<i64>>) -> Scorer {
+        let sizes = sets.iter().map(|s| s.iter().sum()).collect();
+        Scorer { sets, sizes }
+    }
 
-        let mut power = 1;
-        for (_, count) in maxs.into_iter() {
-            power *= count;
+    fn score(&self, counts: &[i64]) -> f64 {
+        let colors: f64 = counts.iter().enumerate()
+            .map(|(i, n)| self.sets.iter().map(|s| ln_choose(*n, s[i])).sum::<f64>())
+            .sum();
+        let total = counts.iter().sum();
+        colors - self.sizes.iter().map(|n| ln_choose(total, *n)).sum::<f64>()
+    }
+
+    // The proportions bags tend to as they grow likelier without bound, each
+    // color's share of every cube drawn, and the score they tend to. Minus
+    // infinity when no cubes were drawn at all.
+    fn limit(&self) -> (Vec<f64>, f64) {
+        let drawn: i64 = self.sizes.iter().sum();
+        if drawn == 0 {
+            return (Vec::new(), f64::NEG_INFINITY);
         }
+        let colors = self.sets[0].len();
+        let proportions: Vec<f64> = (0..colors)
+            .map(|i| self.sets.iter().map(|s| s[i]).sum::<i64>() as f64 / drawn as f64)
+            .collect();
+        let ln_factorial = |n: i64| (2..=n).map(|j| (j as f64).ln()).sum::<f64>();
+        let score = self.sets.iter().zip(&self.sizes)
+            .map(|(set, n)| ln_factorial(*n) + set.iter().zip(&proportions)
+                .filter(|(x, _)| **x > 0)
+                .map(|(x, p)| *x as f64 * p.ln() - ln_factorial(*x))
+                .sum::<f64>())
+            .sum();
+        (proportions, score)
+    }
+}
 
-        sum += power;
+// Steps between neighboring bags: a cube of one color more or less, or one of
+// every color.
+fn steps(colors: usize) -> Vec<Vec<i64>> {
+    let mut steps = Vec::new();
+    for i in 0..colors {
+        for d in [1, -1] {
+            let mut step = vec![0; colors];
+            step[i] = d;
+            steps.push(step);
+        }
+    }
+    steps.push(vec![1; colors]);
+    steps.push(vec![-1; colors]);
+    steps
+}
 
+// Climbs from the smallest possible bag to the likeliest one, each step doubling
+// while the score improves, going no further than `MAX_COUNT`. None when the bag
+// it stops at is no likelier than the limit, so bigger bags keep winning.
+fn likeliest(scorer: &Scorer, lows: &[i64]) -> Option<Vec<i64>> {
+    let mut counts = lows.to_vec();
+    let mut score = scorer.score(&counts);
+    loop {
+        let mut improved = false;
+        // growing or shrinking the whole bag by an eighth follows the ridge of
+        // bags in the same proportions, which single colors only zigzag up
+        let scale: Vec<i64> = counts.iter().map(|c| (c / 8).max(1)).collect();
+        let shrink = scale.iter().map(|c| -c).collect();
+        for dir in steps(lows.len()).into_iter().chain([scale, shrink]) {
+            let mut len = 1;
+            loop {
+                let next: Vec<i64> = counts.iter().zip(&dir).map(|(c, d)| c + d * len).collect();
+                if next.iter().zip(lows).any(|(n, low)| n < low) || next.iter().any(|n| *n > MAX_COUNT) {
+                    break;
+                }
+                let next_score = scorer.score(&next);
+                if next_score <= score {
+                    break;
+                }
+                (counts, score) = (next, next_score);
+                len *= 2;
+                improved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
     }
+    (score > scorer.limit().1).then_some(counts)
+}
 
-    sum
+#[derive(Debug)]
+enum Ranking {
+    // Best first, `complete` false when `MAX_SCORED` ran out before the top was
+    // known to be in.
+    Bags { bags: Vec<(Bag, f64)>, complete: bool },
+    // No bag is likeliest; the proportions by color and the score bags tend to.
+    Unbounded { proportions: Vec<(String, f64)>, score: f64 },
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+// The `top` most likely bags holding only the observed colors, with their
+// log-likelihood. Walks down from the likeliest bag, always to the best neighbor
+// of the bags seen so far, until none of them could make the top.
+fn rank_bags(games: &[Game], top: usize) -> Ranking {
+    let maxima = color_maxima(games);
+    let colors = &maxima.colors;
+    let lows = lower_bounds(&maxima);
+    let scorer = Scorer::new(observed_sets(games, colors));
+
+    let Some(peak) = likeliest(&scorer, &lows) else {
+        let (proportions, score) = scorer.limit();
+        let mut proportions: Vec<(String, f64)> = colors.iter().map(|c| c.to_string()).zip(proportions).collect();
+        proportions.sort_by(|a, b| a.0.cmp(&b.0));
+        return Ranking::Unbounded { proportions, score };
+    };
+    let mut seen = HashSet::from([peak.clone()]);
+    let mut frontier = vec![(scorer.score(&peak), peak)];
+    // best first
+    let mut ranked: Vec<(Vec<i64>, f64)> = Vec::new();
+    let mut complete = true;
+    while let Some(best) = (0..frontier.len()).max_by(|a, b| frontier[*a].0.total_cmp(&frontier[*b].0)) {
+        if ranked.len() >= top && frontier[best].0 <= ranked[top - 1].1 {
+            break;
+        }
+        if seen.len() >= MAX_SCORED {
+            complete = false;
+            break;
+        }
+        let (score, counts) = frontier.swap_remove(best);
+        for step in steps(counts.len()) {
+            let next: Vec<i64> = counts.iter().zip(&step).map(|(c, d)| c + d).collect();
+            let valid = next.iter().zip(&lows).all(|(n, low)| n >= low && *n <= MAX_COUNT);
+            if valid && seen.insert(next.clone()) {
+                frontier.push((scorer.score(&next), next));
+            }
+        }
+        let at = ranked.partition_point(|(_, s)| *s >= score);
+        ranked.insert(at, (counts, score));
+    }
+    ranked.truncate(top);
+
+    let bags = ranked.into_iter()
+        .map(|(counts, score)| {
+            let counts = colors.iter().zip(counts)
+                .map(|(c, n)| (c.to_string(), n as i32))
+                .collect();
+            (Bag { counts }, score)
+        })
+        .collect();
+    Ranking::Bags { bags, complete }
+}
+
+// Ranking of the likeliest bags, or the proportions they tend to when there's no
+// likeliest, and, when `--bag` is given, the score of that bag.
+pub fn infer(content: &str, options: &Options, top: usize) -> Result<String, String> {
+    if top == 0 {
+        return Err("--top has to be at least 1".to_string());
+    }
+    let games = parse_input(content, options.lenient).map_err(|d| d.to_string())?;
+    if games.is_empty() {
+        return Ok("no games to learn from\n".to_string());
+    }
+    let proposed: Option<Bag> = options.bag.as_deref()
+        .map(|bag| bag.parse().map_err(|e| format!("--bag: {e}")))
+        .transpose()?;
+
+    let mut out = String::new();
+    let best = match rank_bags(&games, top) {
+        Ranking::Bags { bags, complete } => {
+            for (i, (bag, score)) in bags.iter().enumerate() {
+                out.push_str(&format!("{:>3}. {}  log-likelihood {:.3}, relative {:.4}\n", i + 1, bag, score, (score - bags[0].1).exp()));
+            }
+            if !complete {
+                out.push_str(&format!("stopped after scoring {} bags, bags likelier than the last few listed may be missing\n", MAX_SCORED));
+            }
+            bags[0].1
+        }
+        Ranking::Unbounded { proportions, score } => {
+            let shares: Vec<String> = proportions.iter().map(|(c, p)| format!("{}={:.4}", c, p)).collect();
+            out.push_str(&format!("no bag is likeliest, bigger ones keep winning; they tend to log-likelihood {:.3} in proportions {}\n", score, shares.join(",")));
+            score
+        }
+    };
+    if let Some(bag) = proposed {
+        let score = log_likelihood(&games, &bag);
+        out.push_str(&format!("proposed {}  log-likelihood {:.3}, relative {:.4}\n", bag, score, (score - best).exp()));
+    }
+    Ok(out)
+}
+
+// QUERY ---------------------------------------
+
+
+// The games matching `expr`, one per line, then how many there are.
+pub fn query(content: &str, expr: &str, options: &Options) -> Result<String, String> {
+    let games = parse_input(content, options.lenient).map_err(|d| d.to_string())?;
+    let query = day2_query::parse(expr).map_err(|d| d.to_string())?;
+    let mut out = String::new();
+    let mut count = 0;
+    for game in &games {
+        if query.matches(game).map_err(|e| format!("game {}: {}", game.id, e))? {
+            out.push_str(&format!("{}\n", game));
+            count += 1;
+        }
+    }
+    out.push_str(&format!("{} game(s) match\n", count));
+    Ok(out)
+}
+
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    match parse_input(content, false) {
+        Ok(_) => Vec::new(),
+        Err(ds) => ds.0.iter().map(|d| d.to_string()).collect(),
+    }
 }
 
 // TESTS ----------------------------------------
@@ -159,7 +586,16 @@ pub fn part2(content: String) {
 mod tests {
     use crate::day2::*;
 
-    static SAMPLE: &'static str = r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    fn part1_inner(content: &str) -> Checked {
+        let bag = DEFAULT_BAG.parse().unwrap();
+        part1_solve(&feasible_ids_batch(&parse_input(content, false).unwrap(), &[bag], UnknownColor::Error).unwrap()[0])
+    }
+
+    fn part2_inner(content: &str) -> Checked {
+        part2_solve(&parse_input(content, false).unwrap(), &Aggregation::Product, Absent::One)
+    }
+
+    static SAMPLE: &str = r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
 Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
 Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
@@ -168,11 +604,192 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
 
     #[test]
     fn part1_sample() {
-        assert_eq!(8, part1_inner(SAMPLE));
+        assert_eq!(Ok(8), part1_inner(SAMPLE));
     }
 
     #[test]
     fn part2_sample() {
-        assert_eq!(2286, part2_inner(SAMPLE));
+        assert_eq!(Ok(2286), part2_inner(SAMPLE));
+    }
+
+    #[test]
+    fn bag_and_unknown_colors() {
+        let games = parse_input("Game 1: 3 blue, 2 yellow\nGame 2: 5 red\nGame 3: 9 red, 1 blue\n", false).unwrap();
+        let bag: Bag = "red=8, blue=4".parse().unwrap();
+        assert_eq!("blue=4,red=8", bag.to_string());
+        let maxima = color_maxima(&games);
+        assert_eq!(Ok(vec![2]), feasible_ids(&maxima, &bag, UnknownColor::Reject));
+        assert_eq!(Ok(vec![1, 2]), feasible_ids(&maxima, &bag, UnknownColor::Ignore));
+        assert!(feasible_ids(&maxima, &bag, UnknownColor::Error).unwrap_err().contains("game 1 has yellow cubes"));
+        assert!("red=8,red=9".parse::<Bag>().is_err());
+        assert!("red:8".parse::<Bag>().is_err());
+        assert!("red=x".parse::<Bag>().is_err());
+    }
+
+    #[test]
+    fn batch_of_bags() {
+        let games = parse_input(SAMPLE, false).unwrap();
+        let maxima = color_maxima(&games);
+        assert_eq!(vec!["blue", "red", "green"], maxima.colors);
+        assert_eq!((3, vec![Some(6), Some(20), Some(13)]), maxima.games[2]);
+
+        let bags = parse_bags("# sample bag\nred=12,green=13,blue=14\n\nred=4,green=3,blue=6\nred=100,green=100,blue=100 # everything\n").unwrap();
+        let feasible = feasible_ids_batch(&games, &bags, UnknownColor::Error).unwrap();
+        assert_eq!(vec![vec![1, 2, 5], vec![1, 2], vec![1, 2, 3, 4, 5]], feasible);
+        assert_eq!("line 2: expected 'color=count', got 'red'", parse_bags("red=1\nred\n").unwrap_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn likelihood_of_bags() {
+        // one red and one blue out of a bag of two: 1/1 for red=1,blue=1, 2*2/C(4,2) = 2/3 for 2 and 2
+        let games = parse_input("Game 1: 1 red, 1 blue\n", false).unwrap();
+        assert!((log_likelihood(&games, &"red=1,blue=1".parse().unwrap()) - 0.0).abs() < 1e-9);
+        assert!((log_likelihood(&games, &"red=2,blue=2".parse().unwrap()) - (2.0f64 / 3.0).ln()).abs() < 1e-9);
+        assert_eq!(f64::NEG_INFINITY, log_likelihood(&games, &"red=1".parse().unwrap()));
+        assert_eq!(f64::NEG_INFINITY, log_likelihood(&games, &"red=0,blue=5".parse().unwrap()));
+
+        let Ranking::Bags { bags: ranking, complete: true } = rank_bags(&games, 2) else { panic!() };
+        assert_eq!(2, ranking.len());
+        assert_eq!("blue=1,red=1", ranking[0].0.to_string());
+        assert!(ranking[0].1 >= ranking[1].1);
+        for (bag, score) in &ranking {
+            assert!((log_likelihood(&games, bag) - score).abs() < 1e-9);
+        }
+        assert!((ln_choose(1_000_000_000, 2) - (1e9f64 * (1e9 - 1.0) / 2.0).ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ranking_matches_brute_force() {
+        // sets vary less than draws with replacement would, so small bags win
+        let games = parse_input("Game 1: 2 red, 2 blue; 3 red, 1 blue; 2 red, 2 blue\nGame 2: 1 red, 3 blue; 2 red, 2 blue; 2 red, 2 blue\n", false).unwrap();
+        let Ranking::Bags { bags: ranking, complete: true } = rank_bags(&games, 20) else { panic!() };
+        assert_eq!(20, ranking.len());
+
+        let mut all = Vec::new();
+        for red in 3..80 {
+            for blue in 3..80 {
+                all.push(log_likelihood(&games, &format!("red={red},blue={blue}").parse().unwrap()));
+            }
+        }
+        all.sort_by(|a, b| b.total_cmp(a));
+        for ((bag, score), expected) in ranking.iter().zip(&all) {
+            assert!((log_likelihood(&games, bag) - score).abs() < 1e-9);
+            assert!((score - expected).abs() < 1e-9);
+        }
+
+    }
+
+    #[test]
+    fn unbounded_likelihood() {
+        // the sets vary more than draws with replacement would, so bigger bags keep winning
+        let single = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\n";
+        let games = parse_input(single, false).unwrap();
+        let Ranking::Unbounded { proportions, score } = rank_bags(&games, 5) else { panic!() };
+        let expected = [("blue", 9.0 / 18.0), ("green", 4.0 / 18.0), ("red", 5.0 / 18.0)];
+        for ((color, p), (c, e)) in proportions.iter().zip(expected) {
+            assert_eq!(c, color);
+            assert!((p - e).abs() < 1e-9);
+        }
+        let big = log_likelihood(&games, &"blue=500000000,green=222222222,red=277777778".parse().unwrap());
+        assert!(big < score && score - big < 1e-6);
+
+        let options = Options { bag: Some("red=4,green=2,blue=6".to_string()), ..Default::default() };
+        let out = infer(single, &options, 5).unwrap();
+        assert!(out.starts_with("no bag is likeliest"));
+        assert!(out.contains("proportions blue=0.5000,green=0.2222,red=0.2778"));
+        assert!(out.contains("proposed blue=6,green=2,red=4  log-likelihood"));
+
+        // the sample too, and its default bag can't have produced it
+        let options = Options { bag: Some(DEFAULT_BAG.to_string()), ..Default::default() };
+        let out = infer(SAMPLE, &options, 5).unwrap();
+        assert!(out.starts_with("no bag is likeliest"));
+        assert!(out.contains("log-likelihood -inf, relative 0.0000"));
+    }
+
+    fn matching_ids(expr: &str) -> Vec<i32> {
+        let query = day2_query::parse(expr).unwrap();
+        parse_input(SAMPLE, false).unwrap().iter()
+            .filter(|g| query.matches(g).unwrap())
+            .map(|g| g.id)
+            .collect()
+    }
+
+    #[test]
+    fn query_games() {
+        assert_eq!(vec![4], matching_ids("blue > 10 in any set"));
+        assert_eq!(vec![1, 2, 3, 4], matching_ids("sets >= 3"));
+        assert_eq!(vec![3, 4], matching_ids("max(red) * max(green) > 40"));
+        assert_eq!(vec![1, 2], matching_ids("not (red > 5 in any set) and (max(blue) <= 4 or id = 1)"));
+        assert_eq!(vec![1, 2, 5], matching_ids("cubes <= 10 in all sets"));
+        assert_eq!(vec![3, 4, 5], matching_ids("(sum(red) + 1) * 2 - 10 > sum(green) and not not id != 2"));
+        assert_eq!(vec![1, 2, 3, 4, 5], matching_ids("min(yellow) = 0"));
+    }
+
+    #[test]
+    fn power_strategies() {
+        let games = parse_input("Game 1: 3 blue, 4 red; 2 green\nGame 2: 5 red\n", false).unwrap();
+        let power = |strategy: &str, absent| part2_solve(&games, &strategy.parse().unwrap(), absent);
+        assert_eq!(Ok(24 + 5), power("product", Absent::One));
+        assert_eq!(Ok(24), power("product", Absent::Zero));
+        assert_eq!(Ok(9 + 7), power("sum", Absent::One));
+        assert_eq!(Ok(4 + 5), power("max", Absent::Zero));
+        assert_eq!(Ok(2 * 4 + 2 * 5), power("weighted:red=2,yellow=10", Absent::Zero));
+        assert_eq!(Ok((2 * 4 + 10) + (2 * 5 + 10)), power("weighted:red=2,yellow=10", Absent::One));
+        assert!("median".parse::<Aggregation>().is_err());
+
+        let maxima = color_maxima(&games);
+        assert_eq!("red=5", minimum_bag(&maxima.colors, &maxima.games[1].1).to_string());
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let d = &parse_input("Game 1: 3 blue\nGame 2: 4 red, green\n", false).unwrap_err().0[0];
+        assert_eq!((2, 16), (d.line, d.col));
+        assert_eq!("Game 2: 4 red, green", d.snippet);
+        assert_eq!(vec!["cubes_count"], d.expected);
+        assert!(d.to_string().contains("2 | Game 2: 4 red, green\n  |                ^"));
+    }
+
+    #[test]
+    fn parse_error_number_overflow() {
+        let d = &parse_input("Game 1: 3 blue\nGame 2: 4000000000 red\n", false).unwrap_err().0[0];
+        assert_eq!((2, 9), (d.line, d.col));
+        assert_eq!(10, d.width);
+        assert!(d.message.starts_with("invalid i32 '4000000000'"));
+    }
+
+    static MALFORMED: &str = r#"Game 1: 3 blue, 4 red
+Game 2: 1 blue, 2; 3 green
+Game 3: 8 green, 6 blue, 20 red
+Game 4 1 green
+Game 5: 6 red, 1 blue
+"#;
+
+    #[test]
+    fn parse_reports_every_record() {
+        let errors = parse_input(MALFORMED, false).unwrap_err().0;
+        assert_eq!(vec![(2, 17), (4, 1)], errors.iter().map(|d| (d.line, d.col)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn parse_lenient_skips_records() {
+        let games = parse_input(MALFORMED, true).unwrap();
+        assert_eq!(vec![1, 3, 5], games.iter().map(|g| g.id).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let games = parse_input(SAMPLE, false).unwrap();
+        let text: String = games.iter().map(|g| format!("{}\n", g)).collect();
+        assert_eq!(SAMPLE, text);
+        assert_eq!(games, parse_input(&text, false).unwrap());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let games = parse_input(SAMPLE, false).unwrap();
+        let json = ast::dump(&games, true);
+        let owned: Vec<Game<'static>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(games, owned);
+    }
+}
@@ -0,0 +1,252 @@
+// Filters over games, for example `blue > 10 in any set`, `sets >= 4` or
+// `max(red) * max(green) > 100`. Colors and `cubes` count within a set, so they
+// only appear in comparisons scoped with `in any set` or `in all sets`.
+
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+use crate::answer::{self, Answer, Overflow};
+use crate::ast;
+use crate::diagnostic::Diagnostic;
+use crate::day2::{Game, Set};
+
+#[derive(Parser)]
+#[grammar = "./day2_query.pest"]
+struct QueryParser;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Or(Vec<Query>),
+    And(Vec<Query>),
+    Not(Box<Query>),
+    Compare { left: Value, op: Cmp, right: Value, scope: Scope },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Game,
+    AnySet,
+    AllSets,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(Answer),
+    Id,
+    Sets,
+    // per set
+    Cubes,
+    Color(String),
+    // over the game's sets, a set without the color counting 0
+    Max(String),
+    Min(String),
+    Sum(String),
+    Add(Box<Value>, Box<Value>),
+    Sub(Box<Value>, Box<Value>),
+    Mul(Box<Value>, Box<Value>),
+}
+
+pub fn parse(text: &str) -> Result<Query, Diagnostic> {
+    let query = QueryParser::parse(Rule::query, text)?.next().unwrap();
+    disjunction(query.into_inner().next().unwrap())
+}
+
+// Children of `pair` without the keywords and operators the grammar keeps.
+fn operands(pair: Pair<'_, Rule>) -> impl Iterator<Item = Pair<'_, Rule>> {
+    pair.into_inner().filter(|p| !matches!(p.as_rule(), Rule::and | Rule::or | Rule::not | Rule::EOI))
+}
+
+fn disjunction(pair: Pair<Rule>) -> Result<Query, Diagnostic> {
+    let mut items = operands(pair).map(conjunction).collect::<Result<Vec<_>, _>>()?;
+    Ok(if items.len() == 1 { items.pop().unwrap() } else { Query::Or(items) })
+}
+
+fn conjunction(pair: Pair<Rule>) -> Result<Query, Diagnostic> {
+    let mut items = operands(pair).map(negation).collect::<Result<Vec<_>, _>>()?;
+    Ok(if items.len() == 1 { items.pop().unwrap() } else { Query::And(items) })
+}
+
+fn negation(pair: Pair<Rule>) -> Result<Query, Diagnostic> {
+    let nots = pair.clone().into_inner().filter(|p| p.as_rule() == Rule::not).count();
+    let inner = operands(pair).next().unwrap();
+    let query = match inner.as_rule() {
+        Rule::disjunction => disjunction(inner)?,
+        _ => comparison(inner)?,
+    };
+    Ok(if nots % 2 == 1 { Query::Not(Box::new(query)) } else { query })
+}
+
+fn comparison(pair: Pair<Rule>) -> Result<Query, Diagnostic> {
+    let mut inner = pair.into_inner();
+    let (left, op, right) = (inner.next().unwrap(), inner.next().unwrap(), inner.next().unwrap());
+    let scope = match inner.next() {
+        None => Scope::Game,
+        Some(s) if s.clone().into_inner().any(|p| p.as_rule() == Rule::any) => Scope::AnySet,
+        Some(_) => Scope::AllSets,
+    };
+    let op = match op.as_str() {
+        "<" => Cmp::Lt,
+        "<=" => Cmp::Le,
+        "=" | "==" => Cmp::Eq,
+        "!=" => Cmp::Ne,
+        ">=" => Cmp::Ge,
+        _ => Cmp::Gt,
+    };
+    let per_set = scope != Scope::Game;
+    Ok(Query::Compare { left: value(left, per_set)?, op, right: value(right, per_set)?, scope })
+}
+
+fn value(pair: Pair<Rule>, per_set: bool) -> Result<Value, Diagnostic> {
+    match pair.as_rule() {
+        Rule::sum | Rule::product => {
+            let mut inner = pair.into_inner();
+            let mut acc = value(inner.next().unwrap(), per_set)?;
+            while let (Some(op), Some(rhs)) = (inner.next(), inner.next()) {
+                let rhs = Box::new(value(rhs, per_set)?);
+                acc = match op.as_str() {
+                    "+" => Value::Add(Box::new(acc), rhs),
+                    "-" => Value::Sub(Box::new(acc), rhs),
+                    _ => Value::Mul(Box::new(acc), rhs),
+                };
+            }
+            Ok(acc)
+        }
+        Rule::number => pair.as_str().parse().map(Value::Number)
+            .map_err(|e| ast::span_error::<Rule>(pair.as_span(), format!("invalid number '{}': {}", pair.as_str(), e))),
+        Rule::call => {
+            let mut inner = pair.into_inner();
+            let function = inner.next().unwrap().as_str();
+            let color = inner.next().unwrap().as_str().to_string();
+            Ok(match function {
+                "max" => Value::Max(color),
+                "min" => Value::Min(color),
+                _ => Value::Sum(color),
+            })
+        }
+        Rule::variable | Rule::color if !per_set && (pair.as_rule() == Rule::color || pair.as_str() == "cubes") => {
+            let message = format!("'{}' counts cubes in a set, add 'in any set' or 'in all sets'", pair.as_str());
+            Err(ast::span_error::<Rule>(pair.as_span(), message))
+        }
+        Rule::variable => Ok(match pair.as_str() {
+            "id" => Value::Id,
+            "sets" => Value::Sets,
+            _ => Value::Cubes,
+        }),
+        _ => Ok(Value::Color(pair.as_str().to_string())),
+    }
+}
+
+// EVALUATION -----------------------------------
+
+impl Query {
+    pub fn matches(&self, game: &Game) -> Result<bool, Overflow> {
+        match self {
+            Query::Or(items) => {
+                for q in items {
+                    if q.matches(game)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Query::And(items) => {
+                for q in items {
+                    if !q.matches(game)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Query::Not(q) => Ok(!q.matches(game)?),
+            Query::Compare { left, op, right, scope } => {
+                let holds = |set: Option<&Set>| -> Result<bool, Overflow> {
+                    Ok(op.holds(left.eval(game, set)?, right.eval(game, set)?))
+                };
+                match scope {
+                    Scope::Game => holds(None),
+                    Scope::AnySet => {
+                        for set in &game.sets {
+                            if holds(Some(set))? {
+                                return Ok(true);
+                            }
+                        }
+                        Ok(false)
+                    }
+                    Scope::AllSets => {
+                        for set in &game.sets {
+                            if !holds(Some(set))? {
+                                return Ok(false);
+                            }
+                        }
+                        Ok(true)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Cmp {
+    fn holds(self, a: Answer, b: Answer) -> bool {
+        match self {
+            Cmp::Lt => a < b,
+            Cmp::Le => a <= b,
+            Cmp::Eq => a == b,
+            Cmp::Ne => a != b,
+            Cmp::Ge => a >= b,
+            Cmp::Gt => a > b,
+        }
+    }
+}
+
+fn count(set: &Set, color: &str) -> Answer {
+    set.cubes.iter().filter(|c| c.color == color).map(|c| c.count as Answer).sum()
+}
+
+impl Value {
+    // `set` is only None outside scoped comparisons, where the parser already
+    // refused colors and `cubes`.
+    fn eval(&self, game: &Game, set: Option<&Set>) -> Result<Answer, Overflow> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Id => Ok(game.id as Answer),
+            Value::Sets => answer::of(game.sets.len()),
+            Value::Cubes => Ok(set.unwrap().cubes.iter().map(|c| c.count as Answer).sum()),
+            Value::Color(c) => Ok(count(set.unwrap(), c)),
+            Value::Max(c) => Ok(game.sets.iter().map(|s| count(s, c)).max().unwrap_or(0)),
+            Value::Min(c) => Ok(game.sets.iter().map(|s| count(s, c)).min().unwrap_or(0)),
+            Value::Sum(c) => answer::sum(game.sets.iter().map(|s| count(s, c))),
+            Value::Add(a, b) => answer::add(a.eval(game, set)?, b.eval(game, set)?),
+            Value::Sub(a, b) => answer::sub(a.eval(game, set)?, b.eval(game, set)?),
+            Value::Mul(a, b) => answer::mul(a.eval(game, set)?, b.eval(game, set)?),
+        }
+    }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors() {
+        let d = parse("blue > 10").unwrap_err();
+        assert_eq!((1, 1), (d.line, d.col));
+        assert!(d.message.contains("'blue' counts cubes in a set"));
+        let d = parse("sets >= ").unwrap_err();
+        assert_eq!((1, 9), (d.line, d.col));
+        assert!(parse("red > 1 in some set").is_err());
+        assert!(parse("and > 1").is_err());
+    }
+}
@@ -1,158 +1,140 @@
-use std::mem;
-
-// SHARED ---------------------------------------
-struct Map<'a> {
-    chars: &'a [u8],
-    pub width: i32,
-    pub height: i32,
+use std::collections::HashMap;
+use pest_derive::Parser;
+use serde::Serialize;
+use crate::answer::{self, Answer, Overflow};
+use crate::ast;
+use crate::diagnostic::Diagnostic;
+use crate::geom::Point;
+use crate::grid::Grid;
+use crate::options::Options;
+
+// PARSING -------------------------------------
+
+#[derive(Parser)]
+#[grammar = "./day3_grammar.pest"]
+pub struct Schematic;
+
+type Map = Grid<u8>;
+
+// The grammar only lets digits, dots and ASCII symbols through, so every cell fits a byte.
+fn parse(content: &str) -> Result<Map, Diagnostic> {
+    Grid::parse::<Schematic, _>(Rule::schematic, Rule::row, content, |c| c as u8)
 }
 
-impl<'a> Map<'a> {
-    pub fn new(chars: &'a [u8]) -> Map<'a> {
-        let width = chars.iter().position(|c| char::from(*c) == '\n').unwrap() as i32;
-        let height = (chars.len() as i32 / (width+1)) as i32; // <- input needs to end with a newline
-        Map { chars, width, height }
-    }
+// What `parse` dumps, the rows as text rather than bytes.
+#[derive(Debug, Serialize)]
+struct MapView<'a> {
+    width: usize,
+    height: usize,
+    rows: Vec<&'a str>
+}
 
-    pub fn get(&self, x: i32, y: i32) -> Option<char> {
-        if x < 0 || y < 0 || x >= self.width || y >= self.height {
-            None
-        } else {
-            self.chars
-                .get(((y * (self.width+1)) + x) as usize)
-                .map(|c| char::from(*c))
-        }
-    }
+pub fn dump(content: &str, _options: &Options, json: bool) -> Result<String, String> {
+    let map = parse(content).map_err(|d| d.to_string())?;
+    let rows = map.rows().map(|row| std::str::from_utf8(row).unwrap()).collect();
+    Ok(ast::dump(&MapView { width: map.width(), height: map.height(), rows }, json))
 }
 
-struct CurrentWord {
-    pub word: Vec<char>,
-    pub mapped_anchors: Vec<(char, i32, i32)>
+// SHARED ---------------------------------------
+
+// A number of the schematic and the symbols around it.
+struct Number {
+    value: Answer,
+    anchors: Vec<(char, Point)>
 }
 
-fn get_anchors(map: &Map, cx: i32, cy: i32) -> Vec<(char, i32, i32)> {
-    let mut anchors = Vec::new();
-    for y in (cy-1)..=(cy+1) {
-        for x in (cx-1)..=(cx+1) {
-            match map.get(x, y) {
-                None | Some('.') => { },
-                Some(d) if d.is_ascii_digit() => { },
-                Some('\n') | Some('\r') => panic!("eeeee"),
-                Some(c) => anchors.push((c, x, y))
-            }
-        }
-    }
+// Numbers next to each symbol, by their index in the list of numbers.
+type SymbolIndex = HashMap<Point, Vec<usize>>;
+
+fn is_symbol(c: u8) -> bool {
+    c != b'.' && !c.is_ascii_digit()
+}
 
-    anchors
+// Symbols on the ring around the number at `start..end` of row `y`, each cell of
+// the ring seen once.
+fn get_anchors(map: &Map, y: i64, start: i64, end: i64) -> Vec<(char, Point)> {
+    let ring = (start - 1..=end)
+        .flat_map(|x| [Point::new(x, y - 1), Point::new(x, y + 1)])
+        .chain([Point::new(start - 1, y), Point::new(end, y)]);
+    ring.filter_map(|p| map.get(p).filter(|c| is_symbol(**c)).map(|c| (char::from(*c), p)))
+        .collect()
 }
 
-fn map_numbers(map: &Map) -> Vec<CurrentWord> {
+// Every number with its anchors, and the index from symbols back to numbers,
+// in one pass over the map.
+fn map_numbers(map: &Map) -> Result<(Vec<Number>, SymbolIndex), Overflow> {
     let mut nums = Vec::new();
-    for y in 0..map.height {
-        let mut current_num: Option<CurrentWord> = None;
-        for x in 0..map.width {
-            match map.get(x, y) {
-                Some(c) if c.is_ascii_digit() => {
-                    match &mut current_num {
-                        Some(w) => {
-                            w.word.push(c);
-                            let anchors = get_anchors(map, x, y);
-                            for anchor in anchors {
-                                if !w.mapped_anchors.contains(&anchor) {
-                                    w.mapped_anchors.push(anchor);
-                                }
-                            }
-                        },
-                        None => {
-                            let word = vec![c];
-                            let mapped_anchors = get_anchors(map, x, y);
-                            current_num = Some(CurrentWord { word, mapped_anchors})
-                        }
-                    }
-                },
-                _ => {
-                    let mut cn: Option<CurrentWord> = None;
-                    mem::swap(&mut cn, &mut current_num);
-                    match cn {
-                        Some(n) => nums.push(n),
-                        _ => { }
-                    }
-                }
+    let mut index = SymbolIndex::new();
+    for (y, row) in map.rows().enumerate() {
+        let y = y as i64;
+        let mut x = 0;
+        while x < row.len() {
+            if !row[x].is_ascii_digit() {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            let mut value: Answer = 0;
+            while x < row.len() && row[x].is_ascii_digit() {
+                value = answer::add(answer::mul(value, 10)?, (row[x] - b'0') as Answer)?;
+                x += 1;
             }
-        }
 
-        match current_num {
-            Some(n) => nums.push(n),
-            _ => { }
+            let anchors = get_anchors(map, y, start as i64, x as i64);
+            for (_, p) in &anchors {
+                index.entry(*p).or_default().push(nums.len());
+            }
+            nums.push(Number { value, anchors });
         }
     }
 
-    nums
+    Ok((nums, index))
 }
 
 // PART 1 --------------------------------------
 
-fn part1_inner(content: &str) -> i32 {
-    let map = Map::new(content.as_bytes());
+fn part1_inner(content: &str) -> Result<Answer, String> {
+    let map = parse(content).map_err(|d| d.to_string())?;
 
-    let nums = map_numbers(&map);
+    let (nums, _) = map_numbers(&map)?;
 
-    nums.iter()
-        .filter(|n| n.mapped_anchors.len() > 0)
-        .map(|n| n.word.iter().collect::<String>().parse::<i32>().unwrap())
-        .sum()
+    Ok(answer::sum(nums.iter()
+        .filter(|n| !n.anchors.is_empty())
+        .map(|n| n.value))?)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, _options: &Options) -> Result<(), String> {
+    println!("result: {}", part1_inner(&content)?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
-fn get_gear_parts(nums: &Vec<CurrentWord>, x: i32, y:i32) -> Vec<&CurrentWord> {
-    let mut found= Vec::new();
-
-    for num in nums {
-        for a in &num.mapped_anchors {
-            match a {
-                ('*', xx, yy) if *xx == x && *yy == y => found.push(num),
-                _ => { }
-            }
-        }
-    }
-
-    found
-}
+fn part2_inner(content: &str) -> Result<Answer, String> {
+    let map = parse(content).map_err(|d| d.to_string())?;
 
-fn part2_inner(content: &str) -> i32 {
-    let map = Map::new(content.as_bytes());
-
-    let nums = map_numbers(&map);
+    let (nums, index) = map_numbers(&map)?;
 
     let mut sum = 0;
-    for y in 0..map.height {
-        for x in 0..map.width {
-            match map.get(x, y) {
-                Some('*') => {
-                    let parts = get_gear_parts(&nums, x, y);
-                    if parts.len() == 2 {
-                        let n1 = parts[0].word.iter().collect::<String>().parse::<i32>().unwrap();
-                        let n2 = parts[1].word.iter().collect::<String>().parse::<i32>().unwrap();
-                        sum += n1 * n2;
-                    }
-                },
-                _ => {}
-            }
+    for (p, parts) in &index {
+        if map.get(*p) == Some(&b'*') && parts.len() == 2 {
+            sum = answer::add(sum, answer::mul(nums[parts[0]].value, nums[parts[1]].value)?)?;
         }
     }
 
-    sum
-
+    Ok(sum)
+}
 
+pub fn part2(content: String, _options: &Options) -> Result<(), String> {
+    println!("result: {}", part2_inner(&content)?);
+    Ok(())
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+// LINT ----------------------------------------
+
+// The grammar names what it expected, the grid catches ragged rows.
+pub fn lint(content: &str) -> Vec<String> {
+    parse(content).err().into_iter().map(|d| d.to_string()).collect()
 }
 
 // TESTS ----------------------------------------
@@ -161,7 +143,7 @@ pub fn part2(content: String) {
 mod tests {
     use crate::day3::*;
 
-    static SAMPLE: &'static str = r#"467..114..
+    static SAMPLE: &str = r#"467..114..
 ...*......
 ..35..633.
 ......#...
@@ -175,11 +157,46 @@ mod tests {
 
     #[test]
     fn part1_sample() {
-        assert_eq!(4361, part1_inner(SAMPLE));
+        assert_eq!(Ok(4361), part1_inner(SAMPLE));
     }
 
     #[test]
     fn part2_sample() {
-        assert_eq!(467835, part2_inner(SAMPLE));
+        assert_eq!(Ok(467835), part2_inner(SAMPLE));
+    }
+
+    // The sample has no symbol on its first and last rows, so tiling it keeps every
+    // number next to the same symbols.
+    #[test]
+    fn tiled_sample() {
+        let rows: Vec<&str> = SAMPLE.lines().collect();
+        let mut tiled = String::new();
+        for _ in 0..20 {
+            for row in &rows {
+                tiled.push_str(&row.repeat(20));
+                tiled.push('\n');
+            }
+        }
+        assert_eq!(Ok(4361 * 400), part1_inner(&tiled));
+        assert_eq!(Ok(467835 * 400), part2_inner(&tiled));
+    }
+
+    #[test]
+    fn symbol_index() {
+        let map = parse("12*3\n..4.\n").unwrap();
+        let (nums, index) = map_numbers(&map).unwrap();
+        assert_eq!(vec![12, 3, 4], nums.iter().map(|n| n.value).collect::<Vec<_>>());
+        assert_eq!(vec![0, 1, 2], index[&Point::new(2, 0)]);
+        assert_eq!(1, index.len());
+        assert_eq!(Ok(0), part2_inner("12*3\n..4.\n"));
+    }
+
+    #[test]
+    fn parse_ragged_row() {
+        let d = parse("467..\n...*\n..35.\n").err().unwrap();
+        assert_eq!((2, 1), (d.line, d.col));
+        assert_eq!("row has 4 cells, expected 5", d.message);
+        assert_eq!((1, 3), parse("46a\n").map(|_| ()).map_err(|d| (d.line, d.col)).unwrap_err());
+        assert_eq!(1, lint("46a\n").len());
     }
 }
\ No newline at end of file
@@ -1,49 +1,47 @@
-use pest::iterators::Pair;
-use pest::Parser;
+use std::fmt;
 use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Checked};
+use crate::ast;
+use crate::diagnostic::Diagnostics;
+use crate::options::Options;
+use crate::from_pair;
 
 // PARSING ---------------------------------------
 #[derive(Parser)]
 #[grammar = "./day4_grammar.pest"]
 pub struct Cards;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Card {
-    _id: i32,
+    id: i32,
     winning_nums: Vec<i32>,
     nums: Vec<i32>
 }
 
-fn parse(content: &str) -> Vec<Card> {
-    let cards_file = Cards::parse(Rule::cards, &content)
-        .expect("couldn't parse content!")
-        .next().unwrap();
+from_pair!(Rule => Card { id: one(card_id), winning_nums: many(winning_num), nums: many(num) });
 
-    let cards = cards_file.into_inner()
-        .filter(|r| r.as_rule() == Rule::card)
-        .map(|r| parse_card(r))
-        .collect();
-
-    cards
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Card {}:", self.id)?;
+        for n in &self.winning_nums {
+            write!(f, " {}", n)?;
+        }
+        write!(f, " |")?;
+        for n in &self.nums {
+            write!(f, " {}", n)?;
+        }
+        Ok(())
+    }
 }
 
-fn parse_rule_as_i32(r: Pair<Rule>) -> i32 {
-    r.as_str().parse().unwrap()
+fn parse(content: &str, lenient: bool) -> Result<Vec<Card>, Diagnostics> {
+    ast::parse_records::<Cards, _, _>(Rule::record, content).accept(lenient)
 }
-fn parse_card(card: Pair<Rule>) -> Card {
-    let mut winning_nums = Vec::new();
-    let mut nums = Vec::new();
-    let mut id: Option<i32> = None;
-    for r in card.into_inner() {
-        match r.as_rule() {
-            Rule::num => { nums.push(parse_rule_as_i32(r)); }
-            Rule::winning_num => { winning_nums.push(parse_rule_as_i32(r)); }
-            Rule::card_id => { id = Some(parse_rule_as_i32(r)); }
-            _ => {}
-        }
-    }
 
-    Card {_id: id.unwrap(), winning_nums, nums}
+pub fn dump(content: &str, options: &Options, json: bool) -> Result<String, String> {
+    let cards = parse(content, options.lenient).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&cards, json))
 }
 
 // SHARED --------------------------------------
@@ -56,22 +54,24 @@ fn count_winning_nums(card: &Card) -> u32 {
 
 
 // PART 1 --------------------------------------
-fn part1_inner(content: &str) -> i32 {
-    parse(content).iter()
+fn part1_solve(cards: &[Card]) -> Checked {
+    let points = cards.iter()
         .map(count_winning_nums)
-        .map(|w| match w { 0 => 0, _ => 2i32.pow(w-1)})
-        .sum()
+        .map(|w| match w { 0 => Ok(0), _ => answer::pow(2, w-1)})
+        .collect::<Result<Vec<_>, _>>()?;
+    answer::sum(points)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    let cards = parse(&content, options.lenient).map_err(|d| d.to_string())?;
+    println!("result: {}", part1_solve(&cards).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
 
-fn part2_inner(content: &str) -> i32 {
-    let cards = parse(content);
+fn part2_solve(cards: &[Card]) -> Checked {
     let mut queue: Vec<usize> = (0..cards.len()).collect();
     queue.sort();
 
@@ -90,21 +90,40 @@ fn part2_inner(content: &str) -> i32 {
         qi += 1;
     }
 
-    queue.len() as i32
+    answer::of(queue.len())
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    let cards = parse(&content, options.lenient).map_err(|d| d.to_string())?;
+    println!("result: {}", part2_solve(&cards).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    match parse(content, false) {
+        Ok(_) => Vec::new(),
+        Err(ds) => ds.0.iter().map(|d| d.to_string()).collect(),
+    }
+}
+
 // TESTS ----------------------------------------
 
 #[cfg(test)]
 mod tests {
     use crate::day4::*;
 
-    static SAMPLE: &'static str = r#"
+    fn part1_inner(content: &str) -> Checked {
+        part1_solve(&parse(content, false).unwrap())
+    }
+
+    fn part2_inner(content: &str) -> Checked {
+        part2_solve(&parse(content, false).unwrap())
+    }
+
+    static SAMPLE: &str = r#"
 Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
 Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
@@ -115,11 +134,19 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
 
     #[test]
     fn part1_sample() {
-        assert_eq!(13, part1_inner(SAMPLE));
+        assert_eq!(Ok(13), part1_inner(SAMPLE));
     }
 
     #[test]
     fn part2_sample() {
-        assert_eq!(30, part2_inner(SAMPLE));
+        assert_eq!(Ok(30), part2_inner(SAMPLE));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn display_round_trip() {
+        let cards = parse(SAMPLE, false).unwrap();
+        let text: String = cards.iter().map(|c| format!("{}\n", c)).collect();
+        assert!(text.starts_with("Card 1: 41 48 83 86 17 | 83 86 6 31 17 9 48 53\n"));
+        assert_eq!(cards, parse(&text, false).unwrap());
+    }
+}
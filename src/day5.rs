@@ -1,169 +1,225 @@
-use std::collections::HashMap;
-use pest::iterators::Pair;
-use pest::Parser;
+use std::borrow::Cow;
+use std::fmt;
 use pest_derive::Parser;
-use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Answer};
+use crate::ast;
+use crate::diagnostic::Diagnostic;
+use crate::interval::{Interval, RangeSet};
+use crate::options::Options;
+use crate::from_pair;
 
 // PARSING ---------------------------------------
 #[derive(Parser)]
 #[grammar = "./day5_grammar.pest"]
 pub struct InputFile;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct IdMap {
     source_range_start: u64,
     target_range_start: u64,
     range_length: u64,
 }
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct CategoryMap<'a> {
-    source_category_name: &'a str,
-    target_category_name: &'a str,
+    source_category_name: Cow<'a, str>,
+    target_category_name: Cow<'a, str>,
     maps: Vec<IdMap>
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Input<'a> {
     seeds: Vec<u64>,
     maps: Vec<CategoryMap<'a>>,
-    categories: HashMap<&'a str, usize>
 }
 
-fn parse(content: &str) -> Input {
-    let file = InputFile::parse(Rule::file, &content)
-        .expect("couldn't parse content!")
-        .next().unwrap();
-
-    let mut seeds: Vec<u64> = Vec::new();
-    let mut maps: Vec<CategoryMap> = Vec::new();
-    let mut categories: HashMap<&str, usize> = HashMap::new();
-    for r in file.into_inner() {
-        match r.as_rule() {
-            Rule::seed_id => { seeds.push(parse_rule_as_u64(r)); },
-            Rule::category => {
-                maps.push(parse_category_map(r));
-                let cat = maps.last().unwrap();
-                categories.insert(cat.source_category_name, maps.len() - 1);
-            },
-            _ => { }
-        };
+from_pair!(Rule => IdMap {
+    source_range_start: one(source_range_start),
+    target_range_start: one(target_range_start),
+    range_length: one(range_length),
+});
+from_pair!(Rule => CategoryMap<'i> {
+    source_category_name: one(source_category_name),
+    target_category_name: one(target_category_name),
+    maps: many(category_ids_map),
+});
+from_pair!(Rule => Input<'i> { seeds: many(seed_id), maps: many(category) });
+
+impl fmt::Display for Input<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seeds:")?;
+        for seed in &self.seeds {
+            write!(f, " {}", seed)?;
+        }
+        for map in &self.maps {
+            write!(f, "\n\n{}", map)?;
+        }
+        Ok(())
     }
-
-    Input { seeds, maps, categories }
 }
 
-fn parse_category_map(map_rule: Pair<Rule>) -> CategoryMap {
-    let mut source = None;
-    let mut target = None;
-    let mut maps = Vec::new();
-
-    for r in map_rule.into_inner() {
-        match r.as_rule() {
-            Rule::source_category_name => { source = Some(r.as_str()); }
-            Rule::target_category_name => { target = Some(r.as_str()); }
-            Rule::category_ids_map => { maps.push(parse_category_ids_map(r)); }
-            _ => { }
-        };
+impl fmt::Display for CategoryMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-to-{} map:", self.source_category_name, self.target_category_name)?;
+        for map in &self.maps {
+            write!(f, "\n{}", map)?;
+        }
+        Ok(())
     }
+}
 
-    CategoryMap { source_category_name: source.unwrap(), target_category_name: target.unwrap(), maps}
+impl fmt::Display for IdMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.target_range_start, self.source_range_start, self.range_length)
+    }
 }
 
-fn parse_category_ids_map(id_map_rule: Pair<Rule>) -> IdMap {
-    let mut source_range_start = 0;
-    let mut target_range_start = 0;
-    let mut range_length = 0;
-
-    for r in id_map_rule.into_inner() {
-        match r.as_rule() {
-            Rule::source_range_start => { source_range_start = parse_rule_as_u64(r); }
-            Rule::target_range_start => { target_range_start = parse_rule_as_u64(r); }
-            Rule::range_length => { range_length = parse_rule_as_u64(r); }
-            _ => { }
-        };
+impl IdMap {
+    // Clamped to u64::MAX, which as an end is left out.
+    pub fn source(&self) -> Interval {
+        Interval::new(self.source_range_start, self.source_range_start.saturating_add(self.range_length))
+    }
+
+    // What to add to a source id to get its target id.
+    pub fn delta(&self) -> i128 {
+        self.target_range_start as i128 - self.source_range_start as i128
     }
+}
 
-    IdMap { source_range_start, target_range_start, range_length }
+fn parse(content: &str) -> Result<Input<'_>, Diagnostic> {
+    ast::parse::<InputFile, _, _>(Rule::file, content)
 }
 
-fn parse_rule_as_u64(r: Pair<Rule>) -> u64 {
-    r.as_str().parse().unwrap()
+pub fn dump(content: &str, _options: &Options, json: bool) -> Result<String, String> {
+    let input = parse(content).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&input, json))
 }
 
 // SHARED --------------------------------------
 
-fn crawl_to_location(init_id: u64, input: &Input) -> u64 {
-    const INIT_CATEGORY: &str = "seed";
-    const END_CATEGORY: &str = "location";
-
-    let mut id = init_id;
+const INIT_CATEGORY: &str = "seed";
+const END_CATEGORY: &str = "location";
 
+// The category maps in the order ids go through them.
+fn category_chain<'a>(input: &'a Input) -> Result<Vec<&'a CategoryMap<'a>>, String> {
+    let mut chain: Vec<&CategoryMap> = Vec::new();
     let mut cat_name = INIT_CATEGORY;
     while !cat_name.eq(END_CATEGORY) {
-        let cat_map: &CategoryMap = input.maps.get(*input.categories.get(cat_name).unwrap()).unwrap();
-        cat_name = cat_map.target_category_name;
-        for range in &cat_map.maps {
-            if range.source_range_start <= id && id < (range.source_range_start + range.range_length) {
-                id = range.target_range_start + (id - range.source_range_start);
-                break;
-            }
+        if chain.iter().any(|m| m.source_category_name == cat_name) {
+            return Err(format!("category '{}' maps back onto itself without reaching '{}'", cat_name, END_CATEGORY));
         }
+        let cat_map: &CategoryMap = input.maps.iter()
+            .find(|m| m.source_category_name == cat_name)
+            .ok_or(format!("no map from category '{}', '{}' can't be reached", cat_name, END_CATEGORY))?;
+        cat_name = &cat_map.target_category_name;
+        chain.push(cat_map);
     }
+    Ok(chain)
+}
 
-    // println!("{init_id} -> {id}");
+fn crawl_to_location(init_id: u64, chain: &[&CategoryMap]) -> Result<u64, String> {
+    let mut id = init_id;
+    for cat_map in chain {
+        if let Some(range) = cat_map.maps.iter().find(|r| r.source().contains(id)) {
+            id = range.target_range_start.checked_add(id - range.source_range_start)
+                .ok_or(format!("map range '{}' moves id {} past u64::MAX", range, id))?;
+        }
+    }
 
-    id
+    Ok(id)
 }
 
 // PART 1 --------------------------------------
 
-fn part1_inner(content: &str) -> u64 {
-    let input = parse(content);
-    input.seeds.iter()
-        .map(|s| crawl_to_location(*s, &input))
-        .min().unwrap()
+fn part1_inner(content: &str) -> Result<Answer, String> {
+    let input = parse(content).map_err(|d| d.to_string())?;
+    let chain = category_chain(&input)?;
+    let locations = input.seeds.iter()
+        .map(|s| crawl_to_location(*s, &chain))
+        .collect::<Result<Vec<_>, _>>()?;
+    let lowest = locations.into_iter().min().ok_or("no seeds to plant")?;
+    Ok(answer::of(lowest)?)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, _options: &Options) -> Result<(), String> {
+    println!("result: {}", part1_inner(&content)?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
-fn part2_inner(content: &str) -> u64 {
-    let input = parse(content);
-    assert_eq!(input.seeds.len() % 2, 0);
-
-    let mut locations = Vec::with_capacity(input.seeds.len() / 2);
-    let mut i = 0;
-    // println!("{:?}", input.seeds);
-
-    while i < input.seeds.len() {
-        let start = *input.seeds.get(i).unwrap();
-        let count = *input.seeds.get(i + 1).unwrap();
-        let end = start + count;
+fn part2_inner(content: &str) -> Result<Answer, String> {
+    let input = parse(content).map_err(|d| d.to_string())?;
+    if input.seeds.len() % 2 != 0 {
+        return Err(format!("part 2 reads seeds as (start, length) pairs but there are {} seeds", input.seeds.len()));
+    }
 
-        let min = (start..end)
-            .into_par_iter()
-            .map(|id| crawl_to_location(id, &input))
-            .min()
-            .unwrap();
+    let mut ids: RangeSet = input.seeds.chunks_exact(2)
+        .map(|pair| Interval::with_len(pair[0], pair[1]).ok_or(format!("seed range {} +{} overflows u64", pair[0], pair[1])))
+        .collect::<Result<_, _>>()?;
+    for cat_map in category_chain(&input)? {
+        ids = map_ranges(&ids, cat_map)?;
+    }
 
-        locations.push(min);
+    Ok(answer::of(ids.min().ok_or("no seeds to plant")?)?)
+}
 
-        i += 2;
+// Moves every part of `ids` covered by a range of the map, first range first,
+// and keeps the rest as is.
+fn map_ranges(ids: &RangeSet, cat_map: &CategoryMap) -> Result<RangeSet, String> {
+    let mut rest = ids.clone();
+    let mut mapped = RangeSet::new();
+    for range in &cat_map.maps {
+        let source = RangeSet::from(range.source());
+        let hit = rest.intersection(&source);
+        rest = rest.difference(&source);
+        let moved = hit.shift(range.delta()).ok_or(format!("map range '{}' moves ids past u64::MAX", range))?;
+        mapped = mapped.union(&moved);
     }
 
-    locations.into_iter()
-        .min()
-        .unwrap()
+    Ok(mapped.union(&rest))
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+pub fn part2(content: String, _options: &Options) -> Result<(), String> {
+    println!("result: {}", part2_inner(&content)?);
+    Ok(())
 }
 
 
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    let input = match parse(content) {
+        Ok(input) => input,
+        Err(d) => return vec![d.to_string()],
+    };
+
+    let mut violations = Vec::new();
+    if input.seeds.len() % 2 != 0 {
+        violations.push(format!("part 2 reads seeds as (start, length) pairs but there are {} seeds", input.seeds.len()));
+    }
+
+    let mut ranges = Vec::with_capacity(input.seeds.len() / 2);
+    for pair in input.seeds.chunks_exact(2) {
+        match pair[0].checked_add(pair[1]) {
+            Some(end) => ranges.push((pair[0], end)),
+            None => violations.push(format!("seed range {} +{} overflows u64", pair[0], pair[1])),
+        }
+    }
+    ranges.sort();
+    for w in ranges.windows(2) {
+        if w[1].0 < w[0].1 {
+            violations.push(format!("seed ranges {}..{} and {}..{} overlap", w[0].0, w[0].1, w[1].0, w[1].1));
+        }
+    }
+
+    if let Err(e) = category_chain(&input) {
+        violations.push(e);
+    }
+
+    violations
+}
+
 // TESTS ----------------------------------------
 
 #[cfg(test)]
@@ -172,7 +228,7 @@ mod tests {
     use std::io::Read;
 use crate::day5::*;
 
-    static SAMPLE: &'static str = r#"
+    static SAMPLE: &str = r#"
 seeds: 79 14 55 13
 
 seed-to-soil map:
@@ -210,12 +266,12 @@ humidity-to-location map:
 
     #[test]
     fn part1_sample() {
-        assert_eq!(35, part1_inner(SAMPLE));
+        assert_eq!(Ok(35), part1_inner(SAMPLE));
     }
 
 
     fn load_input_file() -> String {
-        let mut file = File::open("C:\\Projects\\aoc2023\\data\\day5.txt").unwrap();
+        let mut file = File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/data/day5.txt")).unwrap();
         let mut s = String::new();
         file.read_to_string(&mut s).unwrap();
         s
@@ -233,7 +289,7 @@ humidity-to-location map:
                 .map(|c| c.parse().unwrap())
                 .collect();
 
-            let input = parse(&content);
+            let input = parse(&content).unwrap();
             assert_eq!(r, input.seeds);
         }
 
@@ -242,7 +298,7 @@ humidity-to-location map:
     #[test]
     fn part2_no_overlap() {
         let content = load_input_file();
-        let input = parse(&content);
+        let input = parse(&content).unwrap();
         assert_eq!(0, input.seeds.len() % 2);
 
         for i in (0..input.seeds.len()).step_by(2) {
@@ -269,6 +325,56 @@ humidity-to-location map:
 
     #[test]
     fn part2_sample() {
-        assert_eq!(46, part2_inner(SAMPLE));
+        assert_eq!(Ok(46), part2_inner(SAMPLE));
+    }
+
+    #[test]
+    fn lint_input_file() {
+        assert!(lint(&load_input_file()).is_empty());
+    }
+
+    #[test]
+    fn lint_seed_ranges() {
+        assert!(lint(SAMPLE).is_empty());
+        let content = SAMPLE.replace("seeds: 79 14 55 13", "seeds: 79 14 80 3 5");
+        assert_eq!(vec![
+            "part 2 reads seeds as (start, length) pairs but there are 5 seeds",
+            "seed ranges 79..93 and 80..83 overlap",
+        ], lint(&content));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn seed_errors() {
+        // the grammar wants a seed, but part 2 ranges can be empty
+        let no_seeds = SAMPLE.replace("seeds: 79 14 55 13", "seeds: 79 0");
+        assert_eq!(Err("no seeds to plant".to_string()), part2_inner(&no_seeds));
+        let odd = SAMPLE.replace("seeds: 79 14 55 13", "seeds: 79 14 55");
+        assert_eq!(Err("part 2 reads seeds as (start, length) pairs but there are 3 seeds".to_string()), part2_inner(&odd));
+        let overflow = SAMPLE.replace("seeds: 79 14 55 13", "seeds: 18446744073709551615 1");
+        assert_eq!(Err("seed range 18446744073709551615 +1 overflows u64".to_string()), part2_inner(&overflow));
+    }
+
+    #[test]
+    fn map_range_at_u64_max() {
+        let map = IdMap { source_range_start: u64::MAX - 1, target_range_start: 0, range_length: 5 };
+        assert_eq!(Interval::new(u64::MAX - 1, u64::MAX), map.source());
+        assert!(map.source().contains(u64::MAX - 1));
+    }
+
+    #[test]
+    fn map_errors() {
+        let past_max = "seeds: 5\n\nseed-to-location map:\n18446744073709551615 0 10\n";
+        assert_eq!(Err("map range '18446744073709551615 0 10' moves id 5 past u64::MAX".to_string()), part1_inner(past_max));
+        let no_location = "seeds: 5 1\n\nseed-to-soil map:\n0 0 10\n";
+        assert_eq!(Err("no map from category 'soil', 'location' can't be reached".to_string()), part1_inner(no_location));
+        assert_eq!(Err("no map from category 'soil', 'location' can't be reached".to_string()), part2_inner(no_location));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let input = parse(SAMPLE).unwrap();
+        let text = input.to_string();
+        assert_eq!(SAMPLE.trim(), text);
+        assert_eq!(input, parse(&text).unwrap());
+    }
+}
@@ -1,107 +1,174 @@
+use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Answer};
+use crate::ast;
+use crate::diagnostic::Diagnostic;
+use crate::options::Options;
+use crate::from_pair;
+
 // PARSING -------------------------------------
 
-#[derive(Debug)]
+#[derive(Parser)]
+#[grammar = "./day6_grammar.pest"]
+pub struct SheetFile;
+
+#[derive(Debug, Serialize, Deserialize)]
 struct Race {
     time: u64,
     record_distance: u64
 }
 
-fn parse_separate(content: &str) -> Vec<Race> {
-    let clean_line = content.trim().replace("\r\n", "\n");
-    let lines: Vec<&str> = clean_line.split('\n').collect();
-    assert_eq!(2, lines.len());
-    fn parse_line(line: &str) -> Vec<u64> {
-        line.split(" ")
-            .skip(1)
-            .filter(|s| s.len() > 0)
-            .map(|int| int.parse().unwrap())
-            .collect()
-    }
+#[derive(Debug)]
+struct Sheet<'a> {
+    times: Vec<&'a str>,
+    distances: Vec<&'a str>,
+}
+
+from_pair!(Rule => Sheet<'i> { times: many(time), distances: many(distance) });
 
-    let input: Vec<Vec<u64>> = lines.iter().map(|s| parse_line(s)).collect();
-    let mut races = Vec::new();
-    for i in 0..input[0].len() {
-        races.push(Race { time: input[0][i], record_distance: input[1][i] });
+fn parse_u64(content: &str, digits: &str) -> Result<u64, Diagnostic> {
+    digits.parse()
+        .map_err(|e| ast::slice_error::<Rule>(content, digits, format!("invalid u64 '{}': {}", digits, e)))
+}
+
+fn parse_sheet(content: &str) -> Result<Sheet<'_>, Diagnostic> {
+    let sheet: Sheet = ast::parse::<SheetFile, _, _>(Rule::sheet, content)?;
+    let (longer, shorter) = if sheet.times.len() > sheet.distances.len() {
+        (&sheet.times, &sheet.distances)
+    } else {
+        (&sheet.distances, &sheet.times)
+    };
+    if let Some(extra) = longer.get(shorter.len()) {
+        return Err(ast::slice_error::<Rule>(content, extra, format!("found {} times but {} distances", sheet.times.len(), sheet.distances.len())));
     }
 
-    races
+    Ok(sheet)
+}
 
+fn parse_separate(content: &str) -> Result<Vec<Race>, Diagnostic> {
+    let sheet = parse_sheet(content)?;
+    sheet.times.iter().zip(sheet.distances.iter())
+        .map(|(t, d)| Ok(Race { time: parse_u64(content, t)?, record_distance: parse_u64(content, d)? }))
+        .collect()
 }
 
 
-fn parse_one_race(content: &str) -> Race {
-    let clean_line = content.trim().replace("\r\n", "\n");
-    let lines: Vec<&str> = clean_line.split('\n').collect();
-    assert_eq!(2, lines.len());
-    fn parse_line(line: &str) -> u64 {
-        let s = line.split(" ")
-            .skip(1)
-            .filter(|s| s.len() > 0)
-            .collect::<Vec<&str>>()
-            .join("");
-        s.parse().unwrap()
+fn parse_one_race(content: &str) -> Result<Race, Diagnostic> {
+    let sheet = parse_sheet(content)?;
+    // numbers are separated by spaces only, so the digits from the first to the last
+    // number of a line are a slice of the content we can point at on overflow
+    fn join<'a>(content: &'a str, nums: &[&'a str]) -> Result<u64, Diagnostic> {
+        let first = nums.first().unwrap();
+        let last = nums.last().unwrap();
+        let start = first.as_ptr() as usize - content.as_ptr() as usize;
+        let end = last.as_ptr() as usize - content.as_ptr() as usize + last.len();
+        let kerned = nums.concat();
+        kerned.parse()
+            .map_err(|e| ast::slice_error::<Rule>(content, &content[start..end], format!("invalid u64 '{}': {}", kerned, e)))
     }
 
-    let input: Vec<u64> = lines.iter().map(|s| parse_line(s)).collect();
-    Race { time: input[0], record_distance: input[1] }
+    Ok(Race { time: join(content, &sheet.times)?, record_distance: join(content, &sheet.distances)? })
+}
+
+pub fn dump(content: &str, _options: &Options, json: bool) -> Result<String, String> {
+    let races = parse_separate(content).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&races, json))
 }
 
 // PART 1 --------------------------------------
 
 
-fn count_race_winning_states(race: &Race) -> u64 {
-    let mut wins_count = 0;
-    for pressing_time in 0..=race.time {
-        let velocity = pressing_time;
-        let time_to_run = race.time - pressing_time;
-        let total_distance = time_to_run * velocity;
-        if total_distance > race.record_distance {
-            wins_count += 1;
-        }
+// Holding for p of the time t travels p (t - p), more than the record d for the
+// p strictly between the roots of p^2 - t p + d. The integer square root lands
+// within one of the first winning p, which is then fixed up; the winners are
+// symmetric around t / 2. u64 times u64 always fits in a u128.
+fn count_race_winning_states(race: &Race) -> Answer {
+    let (t, d) = (race.time as u128, race.record_distance as u128);
+    let wins = |p: u128| p * (t - p) > d;
+    if !wins(t / 2) {
+        return 0;
     }
 
-    wins_count
+    // t^2 / 4 >= (t / 2) (t - t / 2) > d, so the discriminant is positive
+    let mut first = (t - (t * t - 4 * d).isqrt()) / 2;
+    while first > 0 && wins(first - 1) {
+        first -= 1;
+    }
+    while !wins(first) {
+        first += 1;
+    }
+    (t - 2 * first + 1) as Answer
 }
 
-fn part1_inner(content: &str) -> u64 {
-    let races = parse_separate(content);
-    races.iter()
-        .fold(1, |acc, race| acc * count_race_winning_states(race))
+fn part1_inner(content: &str) -> Result<Answer, String> {
+    let races = parse_separate(content).map_err(|d| d.to_string())?;
+    Ok(answer::product(races.iter().map(count_race_winning_states))?)
 }
 
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, _options: &Options) -> Result<(), String> {
+    println!("result: {}", part1_inner(&content)?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
-fn part2_inner(content: &str) -> u64 {
-    let race = parse_one_race(content);
-    count_race_winning_states(&race)
+fn part2_inner(content: &str) -> Result<Answer, String> {
+    let race = parse_one_race(content).map_err(|d| d.to_string())?;
+    Ok(count_race_winning_states(&race))
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+pub fn part2(content: String, _options: &Options) -> Result<(), String> {
+    println!("result: {}", part2_inner(&content)?);
+    Ok(())
 }
 
 
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    // part 2 kerns every number of a line together, which has to fit in a u64 too
+    parse_separate(content).err().or_else(|| parse_one_race(content).err())
+        .into_iter().map(|d| d.to_string()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    static SAMPLE: &'static str = r#"
+    static SAMPLE: &str = r#"
 Time:      7  15   30
 Distance:  9  40  200
 "#;
 
     #[test]
     fn part1_sample() {
-        assert_eq!(4 * 8 * 9, part1_inner(SAMPLE));
+        assert_eq!(Ok(4 * 8 * 9), part1_inner(SAMPLE));
+    }
+
+    #[test]
+    fn winning_states_closed_form() {
+        for time in 0..60 {
+            for record_distance in 0..(time * time / 4 + 2) {
+                let brute = (0..=time).filter(|p| p * (time - p) > record_distance).count();
+                assert_eq!(brute as Answer, count_race_winning_states(&Race { time, record_distance }));
+            }
+        }
+        // only holding for 2^32 travels 2^64
+        assert_eq!(1, count_race_winning_states(&Race { time: 1 << 33, record_distance: u64::MAX }));
+        // every p from 2 to t - 2
+        assert_eq!(u64::MAX as Answer - 3, count_race_winning_states(&Race { time: u64::MAX, record_distance: u64::MAX }));
     }
 
     #[test]
     fn part2_sample() {
-        assert_eq!(71503, part2_inner(SAMPLE));
+        assert_eq!(Ok(71503), part2_inner(SAMPLE));
+    }
+
+    #[test]
+    fn parse_missing_distance() {
+        let d = parse_separate("Time:      7  15   30\nDistance:  9  40\n").err().unwrap();
+        assert_eq!((1, 20), (d.line, d.col));
+        assert_eq!("found 3 times but 2 distances", d.message);
     }
 }
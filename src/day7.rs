@@ -1,56 +1,78 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::{HashMap};
+use std::collections::HashMap;
+use std::fmt;
+use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Checked};
+use crate::ast;
+use crate::diagnostic::Diagnostics;
+use crate::memo::Memo;
+use crate::options::Options;
+use crate::from_pair;
 
 // PARSING -------------------------------------
 
+#[derive(Parser)]
+#[grammar = "./day7_grammar.pest"]
+pub struct PlaysFile;
+
 type Card = usize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Hand {
     cards: Vec<Card>
 }
 
-#[derive(Debug)]
-struct Play {
-    hand: Hand,
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Play<'a> {
+    hand: Cow<'a, str>,
     bid: u64
 }
 
-fn parse(content: &str, deck: &'static [char]) -> Vec<Play> {
-    let parse_hand = |hand: &str| -> Hand {
-        assert_eq!(5, hand.len());
-        let cards = hand.chars()
-            .map(|c| deck.iter().position(|cc| *cc == c).unwrap())
-            .collect();
-        Hand { cards }
-    };
-    let parse_play = |c: &str| -> Play {
-        let parts: Vec<&str> = c.split(" ").collect();
-        assert_eq!(2, parts.len());
+from_pair!(Rule => Play<'i> { hand: one(hand), bid: one(bid) });
 
-        let hand = parse_hand(parts[0]);
-        let bid = parts[1].parse().unwrap();
-        Play { hand, bid }
-    };
+impl fmt::Display for Play<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.hand, self.bid)
+    }
+}
+
+fn parse(content: &str, lenient: bool) -> Result<Vec<Play<'_>>, Diagnostics> {
+    ast::parse_records::<PlaysFile, _, _>(Rule::record, content).accept(lenient)
+}
+
+pub fn dump(content: &str, options: &Options, json: bool) -> Result<String, String> {
+    let plays = parse(content, options.lenient).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&plays, json))
+}
+
+fn to_hand(hand: &str, deck: &[char]) -> Hand {
+    let cards = hand.chars()
+        .map(|c| deck.iter().position(|cc| *cc == c).unwrap())
+        .collect();
+    Hand { cards }
+}
 
-    content
-        .replace("\r\n", "\n")
-        .split("\n")
-        .filter(|line|line.len() > 0)
-        .map(parse_play)
-        .collect()
+fn rank_plays(plays: &[Play], deck: &[char], mut sort: impl FnMut(&Hand, &Hand) -> Ordering) -> Checked {
+    let mut hands: Vec<(Hand, u64)> = plays.iter()
+        .map(|p| (to_hand(&p.hand, deck), p.bid))
+        .collect();
+    hands.sort_by(|(h1, _), (h2, _)| sort(h1, h2));
+    hands.iter().enumerate()
+        .try_fold(0, |acc, (i, (_, bid))| answer::add(acc, answer::mul(answer::of(i + 1)?, answer::of(*bid)?)?))
 }
 
 // PART 1 --------------------------------------
 
 
-fn count_cards(cards: &Vec<Card>) -> HashMap<Card, usize> {
+fn count_cards(cards: &[Card]) -> HashMap<Card, usize> {
     let mut map = HashMap::with_capacity(cards.len());
     for c in cards {
        if let Some(v) = map.get_mut(c) {
            *v += 1;
        } else {
-           map.insert(c.clone(), 1);
+           map.insert(*c, 1);
        }
     }
 
@@ -95,43 +117,48 @@ fn sort_by_hand(h1: &Hand, h2: &Hand) -> Ordering {
     }
 }
 
-fn part1_inner(content: &str) -> u64 {
+fn part1_solve(plays: &[Play]) -> Checked {
     //                          0    1    2    3    4    5    6    7    8    9   10   11   12
     const CARDS: [char; 13] = ['2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A'];
-    let mut plays = parse(content, &CARDS);
-    plays.sort_by(|p1, p2| sort_by_hand(&p1.hand, &p2.hand));
-    plays.iter().enumerate()
-        .fold(0, |acc, (i, play)| acc + ((i as u64 + 1)*play.bid))
+    rank_plays(plays, &CARDS, sort_by_hand)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    let plays = parse(&content, options.lenient).map_err(|d| d.to_string())?;
+    println!("result: {}", part1_solve(&plays).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
-fn get_best_hand_type(h: &Hand) -> usize {
-    let jk = h.cards.iter().position(|c| *c == 0);
-    match jk {
-        None => get_hand_type(h),
-        Some(i) => {
-            let mut h2 = h.clone();
-            let mut max = get_hand_type(h);
-            for card_value in 1..13 {
-                h2.cards[i] = card_value;
-                let val = get_best_hand_type(&h2);
-                if val > max {
-                    max = val;
+// Best type for each hand, the same hands come back for every comparison and
+// every joker replacement.
+type HandTypes = Memo<Hand, usize>;
+
+fn get_best_hand_type(types: &mut HandTypes, h: &Hand) -> usize {
+    types.get(h, |types, h| {
+        let jk = h.cards.iter().position(|c| *c == 0);
+        match jk {
+            None => get_hand_type(h),
+            Some(i) => {
+                let mut h2 = h.clone();
+                let mut max = get_hand_type(h);
+                for card_value in 1..13 {
+                    h2.cards[i] = card_value;
+                    let val = get_best_hand_type(types, &h2);
+                    if val > max {
+                        max = val;
+                    }
                 }
+                max
             }
-            max
         }
-    }
+    })
 }
 
-fn sort_by_hand_joker(h1: &Hand, h2: &Hand) -> Ordering {
-    let ht1 = get_best_hand_type(h1);
-    let ht2 = get_best_hand_type(h2);
+fn sort_by_hand_joker(types: &mut HandTypes, h1: &Hand, h2: &Hand) -> Ordering {
+    let ht1 = get_best_hand_type(types, h1);
+    let ht2 = get_best_hand_type(types, h2);
     if ht1 < ht2 {
         Ordering::Less
     } else if ht1 > ht2 {
@@ -147,25 +174,57 @@ fn sort_by_hand_joker(h1: &Hand, h2: &Hand) -> Ordering {
     }
 }
 
-fn part2_inner(content: &str) -> u64 {
+fn part2_solve(plays: &[Play], types: &mut HandTypes) -> Checked {
     //                          0    1    2    3    4    5    6    7    8    9   10   11   12
     const CARDS: [char; 13] = ['J', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'Q', 'K', 'A'];
-    let mut plays = parse(content, &CARDS);
-    plays.sort_by(|p1, p2| sort_by_hand_joker(&p1.hand, &p2.hand));
-    plays.iter().enumerate()
-        .fold(0, |acc, (i, play)| acc + ((i as u64 + 1)*play.bid))
+    rank_plays(plays, &CARDS, |h1, h2| sort_by_hand_joker(types, h1, h2))
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    let plays = parse(&content, options.lenient).map_err(|d| d.to_string())?;
+    let mut types = HandTypes::new();
+    println!("result: {}", part2_solve(&plays, &mut types).map_err(|e| e.to_string())?);
+    if options.verbose {
+        eprintln!("best hand types: {} ({} hands cached)", types.stats(), types.len());
+    }
+    Ok(())
 }
 
 
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    let plays = match parse(content, false) {
+        Ok(plays) => plays,
+        Err(ds) => return ds.0.iter().map(|d| d.to_string()).collect(),
+    };
+
+    // hands are only ordered by type then cards, two equal hands have no rank between them
+    let mut violations = Vec::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (i, play) in plays.iter().enumerate() {
+        if let Some(first) = seen.insert(&play.hand, i) {
+            let message = format!("hand '{}' was already dealt as play #{}", play.hand, first + 1);
+            violations.push(ast::slice_error::<Rule>(content, &play.hand, message).to_string());
+        }
+    }
+
+    violations
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    static SAMPLE: &'static str = r#"
+    fn part1_inner(content: &str) -> Checked {
+        part1_solve(&parse(content, false).unwrap())
+    }
+
+    fn part2_inner(content: &str) -> Checked {
+        part2_solve(&parse(content, false).unwrap(), &mut HandTypes::new())
+    }
+
+    static SAMPLE: &str = r#"
 32T3K 765
 T55J5 684
 KK677 28
@@ -174,12 +233,32 @@ QQQJA 483
 "#;
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn part1_sample() {
-        assert_eq!(765 * 1 + 220 * 2 + 28 * 3 + 684 * 4 + 483 * 5, part1_inner(SAMPLE));
+        assert_eq!(Ok(765 * 1 + 220 * 2 + 28 * 3 + 684 * 4 + 483 * 5), part1_inner(SAMPLE));
     }
 
     #[test]
     fn part2_sample() {
-        assert_eq!(5905, part2_inner(SAMPLE));
+        assert_eq!(Ok(5905), part2_inner(SAMPLE));
+    }
+
+    #[test]
+    fn best_hand_type_is_cached() {
+        let mut types = HandTypes::new();
+        let hand = to_hand("JJ2J3", &['J', '2', '3']);
+        assert_eq!(6, get_best_hand_type(&mut types, &hand));
+        let misses = types.stats().misses;
+        assert_eq!(6, get_best_hand_type(&mut types, &hand));
+        assert_eq!(misses, types.stats().misses);
+        assert!(types.stats().hits > 0);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let plays = parse(SAMPLE, false).unwrap();
+        let text: String = plays.iter().map(|p| format!("{}\n", p)).collect();
+        assert_eq!(SAMPLE.trim_start(), text);
+        assert_eq!(plays, parse(&text, false).unwrap());
     }
 }
@@ -1,7 +1,19 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
+use crate::answer::{self, Answer, Checked};
+use crate::ast::{self, FromPair};
+use crate::cache;
+use crate::diagnostic::Diagnostic;
+use crate::geom::Turn;
+use crate::math::{self, CrtError};
+use crate::search;
+use crate::options::Options;
+use crate::from_pair;
 
 // PARSING -------------------------------------
 
@@ -9,80 +21,116 @@ use pest_derive::Parser;
 #[grammar = "./day8_grammar.pest"]
 pub struct InputFile;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Dir {
-    Left,
-    Right
+#[derive(Debug)]
+struct Node<'a> {
+    id: Cow<'a, str>,
+    left: Cow<'a, str>,
+    right: Cow<'a, str>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Input<'a> {
-    pub directions: Vec<Dir>,
-    pub nodes_map: HashMap<&'a str, Vec<&'a str>>
+    pub directions: Vec<Turn>,
+    pub nodes_map: HashMap<Cow<'a, str>, Vec<Cow<'a, str>>>
+}
+
+impl<'i> FromPair<'i, Rule> for Turn {
+    fn from_pair(pair: Pair<'i, Rule>) -> Result<Self, Diagnostic> {
+        match pair.as_str() {
+            "L" => Ok(Turn::Left),
+            "R" => Ok(Turn::Right),
+            s => Err(ast::span_error::<Rule>(pair.as_span(), format!("unknown direction '{}'", s)))
+        }
+    }
 }
 
-fn parse(content: &str) -> Input {
-    let file = InputFile::parse(Rule::input, &content)
-        .expect("couldn't parse content!")
+from_pair!(Rule => Node<'i> { id: one(node_id), left: one(left_id), right: one(right_id) });
+
+// What Display and `parse` show, the nodes sorted by id.
+#[derive(Debug, Serialize)]
+struct InputView<'a> {
+    directions: &'a [Turn],
+    nodes_map: BTreeMap<&'a str, &'a [Cow<'a, str>]>
+}
+
+impl<'a> Input<'a> {
+    fn view(&'a self) -> InputView<'a> {
+        let nodes_map = self.nodes_map.iter().map(|(id, next)| (id.as_ref(), next.as_slice())).collect();
+        InputView { directions: &self.directions, nodes_map }
+    }
+}
+
+impl fmt::Display for Input<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let view = self.view();
+        for dir in view.directions {
+            write!(f, "{}", dir)?;
+        }
+        writeln!(f)?;
+        for (id, next) in &view.nodes_map {
+            write!(f, "\n{} = ({}, {})", id, next[0], next[1])?;
+        }
+        Ok(())
+    }
+}
+
+fn parse(content: &str) -> Result<Input<'_>, Diagnostic> {
+    let file = InputFile::parse(Rule::input, content)?
         .next().unwrap();
 
     let mut directions = Vec::new();
     let mut nodes_map = HashMap::new();
     for r in file.into_inner() {
         match r.as_rule() {
-            Rule::directions => { parse_directions(r, &mut directions); },
-            Rule::node_map => { parse_nodes_map(r, &mut nodes_map); }
+            Rule::dir => { directions.push(Turn::from_pair(r)?); },
+            Rule::node_map => {
+                let span = r.as_span();
+                let node = Node::from_pair(r)?;
+                if nodes_map.contains_key(&node.id) {
+                    return Err(ast::span_error::<Rule>(span, format!("Found 2 lines with id '{}'", node.id)));
+                }
+                nodes_map.insert(node.id, vec![node.left, node.right]);
+            }
             _ => { }
         };
     }
 
-    Input { directions, nodes_map }
+    Ok(Input { directions, nodes_map })
 }
 
-fn parse_nodes_map<'a>(r: Pair<'a, Rule>, nodes_map: &mut HashMap<&'a str, Vec<&'a str>>) {
-    let mut ids = Vec::with_capacity(3);
-    for rr in r.into_inner() {
-        match rr.as_rule() {
-            Rule::id => { ids.push(rr.as_str()) },
-            _ => unreachable!()
-        };
-    }
-
-    assert_eq!(3, ids.len());
-
-    let key = ids[0];
-    let left_right = ids[1..].into_iter().cloned().collect();
-    match nodes_map.get(key) {
-        None => { nodes_map.insert(key, left_right); }
-        Some(_) => { panic!("Found 2 lines with id '{}'", ids[0]); }
+impl Input<'_> {
+    // Parsing borrows the ids from the input, the cache needs them owned.
+    fn into_owned(self) -> Input<'static> {
+        let nodes_map = self.nodes_map.into_iter()
+            .map(|(id, next)| (Cow::Owned(id.into_owned()), next.into_iter().map(|n| Cow::Owned(n.into_owned())).collect()))
+            .collect();
+        Input { directions: self.directions, nodes_map }
     }
+}
 
+// Parsed input, from the cache when `--cache` is given.
+fn load(content: &str, options: &Options) -> Result<Input<'static>, String> {
+    cache::load_or_build(options.cache.as_deref(), "day8-input", content, || parse(content).map(Input::into_owned))
+        .map_err(|d| d.to_string())
 }
 
-fn parse_directions(r: Pair<Rule>, directions: &mut Vec<Dir>) {
-    for rr in r.into_inner() {
-        match rr.as_rule() {
-            Rule::left => { directions.push(Dir::Left); },
-            Rule::right => { directions.push(Dir::Right); },
-            _ => unreachable!()
-        };
-    }
+pub fn dump(content: &str, _options: &Options, json: bool) -> Result<String, String> {
+    let input = parse(content).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&input.view(), json))
 }
 
 // PART 1 --------------------------------------
 
-fn dir_to_index(dir: Dir) -> usize {
+fn dir_to_index(dir: Turn) -> usize {
     match dir {
-        Dir::Left => 0,
-        Dir::Right => 1
+        Turn::Left => 0,
+        Turn::Right => 1
     }
 }
 
-fn part1_inner(content: &str) -> u64 {
-    let input = parse(content);
-
-    const START_NODE: &'static str = "AAA";
-    const END_NODE: &'static str = "ZZZ";
+fn part1_solve(input: &Input) -> Checked {
+    const START_NODE: &str = "AAA";
+    const END_NODE: &str = "ZZZ";
 
     let mut dir_idx = 0usize;
     let mut current_node = START_NODE;
@@ -90,7 +138,7 @@ fn part1_inner(content: &str) -> u64 {
     while !current_node.eq(END_NODE) {
         let choices = input.nodes_map.get(current_node).expect("couldn't find node");
         let choice = dir_to_index(input.directions[dir_idx]);
-        current_node = choices[choice];
+        current_node = &choices[choice];
         steps += 1;
         dir_idx += 1;
         if dir_idx >= input.directions.len() {
@@ -98,11 +146,12 @@ fn part1_inner(content: &str) -> u64 {
         }
     }
 
-    steps
+    answer::of(steps)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    println!("result: {}", part1_solve(&load(&content, options)?).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
@@ -111,168 +160,219 @@ pub fn part1(content: String) {
 enum PathFunction {
     Const(u64),
     Linear(u64, u64),
-    Any(Vec<PathFunction>)
 }
 
 impl PathFunction {
-    pub fn len(&self) -> u64 {
-        match &self {
-            PathFunction::Const(i) => {*i}
-            PathFunction::Linear(s, l) => {s + l},
-            PathFunction::Any(funcs) => {
-                funcs.iter().map(|f| f.len()).max().unwrap()
-            }
-        }
-    }
-    pub fn as_linear(&self) -> (u64, u64) {
-        match &self {
-            PathFunction::Linear(x, y) => (*x, *y),
-            _ => panic!("..."),
-        }
-    }
-
-    pub fn get_biggest(&self) -> &PathFunction {
-        match &self {
-            PathFunction::Const(_) => panic!("..."),
-            PathFunction::Linear(_, _)  => &self,
-            PathFunction::Any(funcs) => funcs.iter().max_by(|f1, f2| f1.len().cmp(&f2.len())).unwrap()
+    // Whether the lane is on the function's end node after `steps` steps.
+    pub fn accepts(&self, steps: u64) -> bool {
+        match *self {
+            PathFunction::Const(c) => steps == c,
+            PathFunction::Linear(s, l) => steps >= s && (steps - s).is_multiple_of(l),
         }
     }
 }
 
-fn part2_inner(content: &str) -> u64 {
-    let input = parse(content);
-    const START_NODE: &'static str = "A";
-    const END_NODE: &'static str = "Z";
+const LANE_START: &str = "A";
+const LANE_END: &str = "Z";
 
-    let lanes: Vec<&str> = input.nodes_map.keys()
-        .filter(|k| k.ends_with(START_NODE))
-        .cloned()
-        .collect();
+// One step of a walk, the state being the node and the index of the next direction.
+fn step<'a>(input: &'a Input, (node, dir_idx): (&str, usize)) -> (&'a str, usize) {
+    let choices = input.nodes_map.get(node).expect("couldn't find node");
+    let choice = dir_to_index(input.directions[dir_idx]);
+    (&choices[choice], (dir_idx + 1) % input.directions.len())
+}
 
-    let mut functions: Vec<PathFunction> = Vec::with_capacity(lanes.len());
-    for lane in &lanes {
-        let mut dir_idx = 0usize;
-        let mut current_node = *lane;
-        let mut path: Vec<(&str, usize)> = Vec::new();
-        while !path.contains(&(current_node, dir_idx)) {
-            path.push((current_node, dir_idx));
-            let choices = input.nodes_map.get(current_node).expect("couldn't find node");
-            let choice = dir_to_index(input.directions[dir_idx]);
-            current_node = choices[choice];
-
-            dir_idx += 1;
-            if dir_idx >= input.directions.len() {
-                dir_idx = 0;
+// Walks the lane starting at `lane` until it loops and returns one function per
+// end node met on the way (see below), empty when the lane never meets one.
+fn lane_functions(input: &Input, lane: &str) -> Vec<PathFunction> {
+    let cycle = search::brent((lane, 0), |s| step(input, *s));
+    let path = std::iter::successors(Some((lane, 0)), |s| Some(step(input, *s)))
+        .take(cycle.start + cycle.length);
+
+    // Extract "functions" / "equations" out of loops / lanes
+    // 1. No loop
+    // 2. No Z
+    // 3. N Z
+
+    // 1 2 3 Z1 4 Z2 / 3 Z1 4 Z2 / 3 Z1 4 Z2
+    // Offset: 1 2
+    // Loop: 3 Z 4 Z
+    // fz1(n) = Offset(lane_begin) + Offset(loop_begin) + loop_length * (n-1)
+    // fz1(n) = loop_start + (z1_pos - loop_start) + loop_length * (n-1)
+        // where n >= 1
+    // fz1(1) = Offset + |3 Z1| = 2 + 2 = 4
+    // fz1(2) = Offset + |3 Z1 4 Z2 3 Z1| = 2 + 6 = 8
+    // fz1(3) = Offset + |3 Z1 4 Z2 3 Z1 4 Z2 3 Z1| = 2 + 10 = 12
+
+    let loop_begin = cycle.start;
+    let loop_size = cycle.length;
+
+    let mut local_functions: Vec<PathFunction> = Vec::new();
+
+    for (i, (n, _)) in path.enumerate() {
+        if n.ends_with(LANE_END) {
+            if i < loop_begin {
+                local_functions.push(PathFunction::Const(i as u64))
+            } else {
+                local_functions.push(PathFunction::Linear(i as u64, loop_size as u64))
             }
-
         }
+    }
 
-        // println!("Path: {:?}", path);
-        assert!(path.iter().any(|(p, _)| p.ends_with(END_NODE)));
-
+    local_functions
+}
 
-        // Extract "functions" / "equations" out of loops / lanes
-        // 1. No loop
-        // 2. No Z
-        // 3. N Z
+// Sorted, for lint to report them in a stable order.
+fn lanes<'a>(input: &'a Input) -> Vec<&'a str> {
+    let mut lanes: Vec<&str> = input.nodes_map.keys()
+        .filter(|k| k.ends_with(LANE_START))
+        .map(|k| k.as_ref())
+        .collect();
+    lanes.sort_unstable();
+    lanes
+}
 
-        // 1 2 3 Z1 4 Z2 / 3 Z1 4 Z2 / 3 Z1 4 Z2
-        // Offset: 1 2
-        // Loop: 3 Z 4 Z
-        // fz1(n) = Offset(lane_begin) + Offset(loop_begin) + loop_length * (n-1)
-        // fz1(n) = loop_start + (z1_pos - loop_start) + loop_length * (n-1)
-            // where n >= 1
-        // fz1(1) = Offset + |3 Z1| = 2 + 2 = 4
-        // fz1(2) = Offset + |3 Z1 4 Z2 3 Z1| = 2 + 6 = 8
-        // fz1(3) = Offset + |3 Z1 4 Z2 3 Z1 4 Z2 3 Z1| = 2 + 10 = 12
+fn part2_solve(input: &Input) -> Result<Answer, String> {
+    let functions: Vec<Vec<PathFunction>> = lanes(input).into_iter()
+        .map(|lane| lane_functions(input, lane))
+        .collect();
 
-        let loop_begin = path.iter().position(|x| x.eq(&(current_node, dir_idx))).unwrap();
-        let loop_size = path.len() - loop_begin;
+    let steps = solve_functions(&functions)
+        .map_err(|e| format!("can't combine the lanes: {}", e))?
+        .ok_or("lanes never all end on the same step")?;
+    Ok(answer::of(steps)?)
+}
 
-        let mut local_functions: Vec<PathFunction> = Vec::with_capacity(lanes.len());
+// Step counts accepted by every lane combined so far: a single one, or every
+// count from `from` on that is `r` mod `m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Steps {
+    Exactly(u64),
+    Congruent { r: u64, m: u64, from: u64 },
+}
 
-        for (i, (n, _)) in path.iter().enumerate() {
-            if n.ends_with(END_NODE) {
-                if i < loop_begin {
-                    local_functions.push(PathFunction::Const(i as u64))
-                } else {
-                    local_functions.push(PathFunction::Linear(i as u64, loop_size as u64))
+impl Steps {
+    // The counts accepted by both, None when there are none.
+    fn and(self, f: &PathFunction) -> Result<Option<Steps>, CrtError> {
+        match (self, f) {
+            (Steps::Exactly(c), _) => Ok(f.accepts(c).then_some(Steps::Exactly(c))),
+            (Steps::Congruent { r, m, from }, &PathFunction::Const(c)) => {
+                Ok((c >= from && c % m == r).then_some(Steps::Exactly(c)))
+            },
+            (Steps::Congruent { r, m, from }, &PathFunction::Linear(s, l)) => {
+                match math::crt(&[(r, m), (s, l)]) {
+                    Ok((r, m)) => Ok(Some(Steps::Congruent { r, m, from: from.max(s) })),
+                    Err(CrtError::Unsatisfiable { .. }) => Ok(None),
+                    Err(e) => Err(e),
                 }
-            }
+            },
         }
+    }
 
-        if local_functions.len() > 1 {
-            functions.push(PathFunction::Any(local_functions));
-        } else if local_functions.len() == 1 {
-            functions.push(local_functions[0].clone());
+    // None when it's past u64::MAX.
+    fn smallest(self) -> Option<u64> {
+        match self {
+            Steps::Exactly(c) => Some(c),
+            Steps::Congruent { r, m, from } => {
+                let k = if r >= from { 0 } else { (from - r).div_ceil(m) };
+                r.checked_add(k.checked_mul(m)?)
+            },
         }
-
     }
-
-    solve_functions(&functions)
 }
 
-fn is_const(f: &&PathFunction) -> bool {
-    match f {
-        PathFunction::Const(_) => true,
-        _ => false
+// Smallest step count where every lane is on an end node, given the functions of
+// each lane's end nodes. Lanes are combined one at a time, keeping only the
+// distinct combinations still satisfiable. None when there is no such count,
+// an error when a combination no longer fits in a u64.
+fn solve_functions(lanes: &[Vec<PathFunction>]) -> Result<Option<u64>, CrtError> {
+    if lanes.is_empty() {
+        return Ok(None);
     }
-}
-fn solve_functions(functions: &Vec<PathFunction>) -> u64 {
-    let mut functions = functions.clone();
-
-    // sort from biggest loop to smallest
-    functions.sort_by(|a, b| b.len().cmp(&a.len()));
-
-    // hope no constant fn
-    assert_eq!(0, functions.iter().filter(is_const).count());
-
-    let mut mult = 0;
-    let mut done = false;
-    let (biggest_loop_start, biggest_loop_len) = functions[0].get_biggest().as_linear();
-
-    while !done {
-        let target = biggest_loop_start + (mult * biggest_loop_len);
-        done = true;
-        for func in functions.iter().skip(1).map(|f| f) {
-            done = solve_function(target, func);
-            if !done {
-                mult += 1;
-                break;
+
+    let mut combined = vec![Steps::Congruent { r: 0, m: 1, from: 0 }];
+    for functions in lanes {
+        let mut next = Vec::new();
+        for steps in &combined {
+            for f in functions {
+                next.extend(steps.and(f)?);
             }
         }
+        next.sort_unstable();
+        next.dedup();
+        combined = next;
     }
 
-    biggest_loop_start + (mult * biggest_loop_len)
+    match combined.iter().filter_map(|s| s.smallest()).min() {
+        None if !combined.is_empty() => Err(CrtError::Overflow),
+        smallest => Ok(smallest),
+    }
+}
 
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    println!("result: {}", part2_solve(&load(&content, options)?)?);
+    Ok(())
 }
 
-fn solve_function(target: u64, func: &PathFunction) -> bool {
-    match func {
-        PathFunction::Const(_) => {
-            panic!("Not supported for now!");
+
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    let input = match parse(content) {
+        Ok(input) => input,
+        Err(d) => return vec![d.to_string()],
+    };
+
+    let mut violations = Vec::new();
+    for (id, nexts) in &input.nodes_map {
+        for next in nexts {
+            if !input.nodes_map.contains_key(next) {
+                violations.push(format!("node '{}' leads to unknown node '{}'", id, next));
+            }
         }
-        PathFunction::Linear(start, len) => {
-            let m = target - start;
-            m % len == 0
+    }
+    if !violations.is_empty() {
+        return violations;
+    }
+
+    for node in ["AAA", "ZZZ"] {
+        if !input.nodes_map.contains_key(node) {
+            violations.push(format!("part 1 needs a node '{}'", node));
         }
-        PathFunction::Any(funcs) => {
-            funcs.iter().any(|f| solve_function(target, f))
+    }
+
+    let mut functions = Vec::new();
+    for lane in lanes(&input) {
+        let lane_functions = lane_functions(&input, lane);
+        if lane_functions.is_empty() {
+            violations.push(format!("lane '{}' loops without ever reaching a node ending with '{}'", lane, LANE_END));
+        }
+        functions.push(lane_functions);
+    }
+    if violations.is_empty() {
+        match solve_functions(&functions) {
+            Ok(None) => violations.push("lanes never all reach an end node on the same step".to_string()),
+            Err(e) => violations.push(format!("can't combine the lanes: {}", e)),
+            Ok(Some(_)) => {},
         }
     }
-}
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+    violations
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    static SAMPLE_1: &'static str = r#"
+    fn part1_inner(content: &str) -> Checked {
+        part1_solve(&parse(content).unwrap())
+    }
+
+    fn part2_inner(content: &str) -> Result<Answer, String> {
+        part2_solve(&parse(content).unwrap())
+    }
+
+    static SAMPLE_1: &str = r#"
 RL
 
 AAA = (BBB, CCC)
@@ -286,10 +386,10 @@ ZZZ = (ZZZ, ZZZ)
 
     #[test]
     fn part1_sample1() {
-        assert_eq!(2, part1_inner(SAMPLE_1));
+        assert_eq!(Ok(2), part1_inner(SAMPLE_1));
     }
 
-    static SAMPLE_2: &'static str = r#"
+    static SAMPLE_2: &str = r#"
 LLR
 
 AAA = (BBB, BBB)
@@ -299,10 +399,10 @@ ZZZ = (ZZZ, ZZZ)
 
     #[test]
     fn part1_sample2() {
-        assert_eq!(6, part1_inner(SAMPLE_2));
+        assert_eq!(Ok(6), part1_inner(SAMPLE_2));
     }
 
-    static SAMPLE_3: &'static str = r#"
+    static SAMPLE_3: &str = r#"
 LR
 
 11A = (11B, XXX)
@@ -316,7 +416,53 @@ XXX = (XXX, XXX)"#;
 
     #[test]
     fn part2_sample3() {
-        assert_eq!(6, part2_inner(SAMPLE_3));
+        assert_eq!(Ok(6), part2_inner(SAMPLE_3));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let input = parse(SAMPLE_1).unwrap();
+        let text = input.to_string();
+        assert_eq!(SAMPLE_1.trim(), text);
+        assert_eq!(input, parse(&text).unwrap());
+    }
+
+    #[test]
+    fn part2_constant_offset() {
+        let content = "L\n\n11A = (11Z, 11A)\n11Z = (11B, 11B)\n11B = (11B, 11B)\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(Ok(1), part2_inner(content));
+        assert!(lint(content).is_empty());
+        assert_eq!(vec!["part 1 needs a node 'AAA'", "part 1 needs a node 'ZZZ'"], lint(SAMPLE_3));
+    }
+
+    #[test]
+    fn many_functions_per_lane() {
+        // 3^40 picks, but sharing the loop length no more than 3 combinations
+        // stay alive: every lane ends on steps 100, 104 or 108 (mod 12)
+        let mut lanes: Vec<Vec<PathFunction>> = (0..40)
+            .map(|i| (0..3).map(|k| PathFunction::Linear(100 + (i + k) % 3 * 4, 12)).collect())
+            .collect();
+        assert_eq!(Ok(Some(100)), solve_functions(&lanes));
+        lanes.push(vec![PathFunction::Linear(101, 12)]);
+        assert_eq!(Ok(None), solve_functions(&lanes));
+        assert_eq!(Ok(Some(3)), solve_functions(&[vec![PathFunction::Const(7), PathFunction::Linear(1, 2)], vec![PathFunction::Linear(3, 4)]]));
+        assert_eq!(Ok(Some(7)), solve_functions(&[vec![PathFunction::Const(7)], vec![PathFunction::Linear(3, 4)]]));
+    }
+
+    #[test]
+    fn overflow_is_not_unsatisfiable() {
+        // coprime loop lengths whose product passes u64::MAX
+        let big = [4294967291, 4294967279, 4294967231];
+        let lanes: Vec<Vec<PathFunction>> = big.iter().map(|l| vec![PathFunction::Linear(1, *l)]).collect();
+        assert_eq!(Err(CrtError::Overflow), solve_functions(&lanes));
+        assert_eq!(Ok(None), solve_functions(&[vec![PathFunction::Linear(0, 2)], vec![PathFunction::Linear(1, 2)]]));
+    }
+
+    #[test]
+    fn lint_lanes_never_meet() {
+        // 11A ends on odd steps, 22A on steps = 2 (mod 4)
+        let content = "L\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)\n22A = (22B, 22B)\n22B = (22Z, 22Z)\n22Z = (22C, 22C)\n22C = (22A, 22A)\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert_eq!(vec!["lanes never all reach an end node on the same step"], lint(content));
     }
 
     // #[test]
@@ -1,95 +1,127 @@
+use pest_derive::Parser;
+use crate::answer::{self, Answer, Checked, Overflow};
+use crate::ast;
+use crate::diagnostic::Diagnostics;
+use crate::options::Options;
+use crate::from_pair;
+
 // PARSE --------------------------------------
-fn parse(content: &str) -> Vec<Vec<i64>> {
-    let content = content.replace("\r\n", "\n");
-    content.split("\n")
-        .filter(|l|l.len() > 0)
-        .map(|l| parse_line(l))
-        .collect()
+
+#[derive(Parser)]
+#[grammar = "./day9_grammar.pest"]
+pub struct Report;
+
+#[derive(Debug)]
+struct History {
+    values: Vec<i64>
+}
+
+from_pair!(Rule => History { values: many(value) });
+
+fn parse(content: &str, lenient: bool) -> Result<Vec<Vec<i64>>, Diagnostics> {
+    let histories: Vec<History> = ast::parse_records::<Report, _, _>(Rule::record, content).accept(lenient)?;
+    Ok(histories.into_iter().map(|h| h.values).collect())
 }
 
-fn parse_line(l: &str) -> Vec<i64> {
-    l.split(" ").map(|w|w.parse().unwrap()).collect()
+pub fn dump(content: &str, options: &Options, json: bool) -> Result<String, String> {
+    let histories = parse(content, options.lenient).map_err(|d| d.to_string())?;
+    Ok(ast::dump(&histories, json))
 }
 
 // PART 1 --------------------------------------
 
-fn find_next(history: &Vec<i64>) -> i64 {
+// Differences of differences, down to the first row of zeroes.
+fn derivatives(history: &[i64]) -> Result<Vec<Vec<Answer>>, Overflow> {
     let mut derivatives = Vec::new();
-    derivatives.push(history.clone());
+    derivatives.push(history.iter().map(|n| *n as Answer).collect::<Vec<_>>());
 
-    while derivatives.last().unwrap().iter().any(|n| *n != 0i64){
+    while derivatives.last().unwrap().iter().any(|n| *n != 0){
         let last = derivatives.last().unwrap();
         let mut deltas = Vec::with_capacity(last.len()-1);
         for i in 1..last.len() {
-            let delta = last[i] - last[i -1];
+            let delta = answer::sub(last[i], last[i -1])?;
             deltas.push(delta);
         }
         derivatives.push(deltas);
     }
 
-    derivatives.last_mut().unwrap().push(0i64);
+    Ok(derivatives)
+}
+
+fn find_next(history: &[i64]) -> Checked {
+    let mut derivatives = derivatives(history)?;
+
+    derivatives.last_mut().unwrap().push(0);
     for i in (0..(derivatives.len()-1)).rev() {
         let delta = *derivatives[i + 1].last().unwrap();
         let last_num = *derivatives[i].last().unwrap();
-        derivatives[i].push(last_num + delta);
+        derivatives[i].push(answer::add(last_num, delta)?);
     }
 
-    *derivatives.first().unwrap().last().unwrap()
+    Ok(*derivatives.first().unwrap().last().unwrap())
 }
 
-fn part1_inner(content: &str) -> i64 {
-    let histories = parse(content);
-    histories.iter().map(find_next).sum()
+fn part1_solve(histories: &[Vec<i64>]) -> Checked {
+    answer::sum(histories.iter().map(|h| find_next(h)).collect::<Result<Vec<_>, _>>()?)
 }
 
-pub fn part1(content: String) {
-    println!("result: {}", part1_inner(&content));
+pub fn part1(content: String, options: &Options) -> Result<(), String> {
+    let histories = parse(&content, options.lenient).map_err(|d| d.to_string())?;
+    println!("result: {}", part1_solve(&histories).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 // PART 2 --------------------------------------
 
-fn find_previous(history: &Vec<i64>) -> i64 {
-    let mut derivatives = Vec::new();
-    derivatives.push(history.clone());
+fn find_previous(history: &[i64]) -> Checked {
+    let mut derivatives = derivatives(history)?;
 
-    while derivatives.last().unwrap().iter().any(|n| *n != 0i64){
-        let last = derivatives.last().unwrap();
-        let mut deltas = Vec::with_capacity(last.len()-1);
-        for i in 1..last.len() {
-            let delta = last[i] - last[i -1];
-            deltas.push(delta);
-        }
-        derivatives.push(deltas);
-    }
-
-    derivatives.last_mut().unwrap().insert(0, 0i64);
+    derivatives.last_mut().unwrap().insert(0, 0);
     for i in (0..(derivatives.len()-1)).rev() {
         let delta = *derivatives[i + 1].first().unwrap();
         let last_num = *derivatives[i].first().unwrap();
-        derivatives[i].insert(0, last_num - delta);
+        derivatives[i].insert(0, answer::sub(last_num, delta)?);
     }
 
-    *derivatives.first().unwrap().first().unwrap()
+    Ok(*derivatives.first().unwrap().first().unwrap())
 }
 
-pub fn part2_inner(content: &str) -> i64 {
-    let histories = parse(content);
-    histories.iter().map(find_previous).sum()
+fn part2_solve(histories: &[Vec<i64>]) -> Checked {
+    answer::sum(histories.iter().map(|h| find_previous(h)).collect::<Result<Vec<_>, _>>()?)
 }
 
-pub fn part2(content: String) {
-    println!("result: {}", part2_inner(&content));
+pub fn part2(content: String, options: &Options) -> Result<(), String> {
+    let histories = parse(&content, options.lenient).map_err(|d| d.to_string())?;
+    println!("result: {}", part2_solve(&histories).map_err(|e| e.to_string())?);
+    Ok(())
 }
 
 
+// LINT ----------------------------------------
+
+pub fn lint(content: &str) -> Vec<String> {
+    match parse(content, false) {
+        Ok(_) => Vec::new(),
+        Err(ds) => ds.0.iter().map(|d| d.to_string()).collect(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    static SAMPLE_1: &'static str = "0 3 6 9 12 15";
-    static SAMPLE_2: &'static str = "1 3 6 10 15 21";
-    static SAMPLE_3: &'static str = "10 13 16 21 30 45";
-    static SAMPLE_ALL: &'static str = r#"
+    fn part1_inner(content: &str) -> Checked {
+        part1_solve(&parse(content, false).unwrap())
+    }
+
+    fn part2_inner(content: &str) -> Checked {
+        part2_solve(&parse(content, false).unwrap())
+    }
+
+    static SAMPLE_1: &str = "0 3 6 9 12 15";
+    static SAMPLE_2: &str = "1 3 6 10 15 21";
+    static SAMPLE_3: &str = "10 13 16 21 30 45";
+    static SAMPLE_ALL: &str = r#"
 0 3 6 9 12 15
 1 3 6 10 15 21
 10 13 16 21 30 45
@@ -97,41 +129,46 @@ mod tests {
 
     #[test]
     fn part1_sample1() {
-        assert_eq!(18, part1_inner(SAMPLE_1));
+        assert_eq!(Ok(18), part1_inner(SAMPLE_1));
     }
 
     #[test]
     fn part1_sample2() {
-        assert_eq!(28, part1_inner(SAMPLE_2));
+        assert_eq!(Ok(28), part1_inner(SAMPLE_2));
     }
 
     #[test]
     fn part1_sample3() {
-        assert_eq!(68, part1_inner(SAMPLE_3));
+        assert_eq!(Ok(68), part1_inner(SAMPLE_3));
     }
 
     #[test]
     fn part1_sample_all() {
-        assert_eq!(114, part1_inner(SAMPLE_ALL));
+        assert_eq!(Ok(114), part1_inner(SAMPLE_ALL));
     }
 
     #[test]
     fn part2_sample1() {
-        assert_eq!(-3, part2_inner(SAMPLE_1));
+        assert_eq!(Ok(-3), part2_inner(SAMPLE_1));
     }
 
     #[test]
     fn part2_sample2() {
-        assert_eq!(0, part2_inner(SAMPLE_2));
+        assert_eq!(Ok(0), part2_inner(SAMPLE_2));
     }
 
     #[test]
     fn part2_sample3() {
-        assert_eq!(5, part2_inner(SAMPLE_3));
+        assert_eq!(Ok(5), part2_inner(SAMPLE_3));
     }
 
     #[test]
     fn part2_sample_all() {
-        assert_eq!(2, part2_inner(SAMPLE_ALL));
+        assert_eq!(Ok(2), part2_inner(SAMPLE_ALL));
+    }
+
+    #[test]
+    fn parse_double_spaces() {
+        assert_eq!(vec![vec![0, -3, 6], vec![1, 2]], parse("0  -3 6 \n\n1 2", false).unwrap());
     }
 }
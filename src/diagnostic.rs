@@ -0,0 +1,79 @@
+use std::fmt;
+use pest::error::{Error, ErrorVariant, LineColLocation};
+use pest::RuleType;
+
+// Human readable report for malformed inputs: where it failed, the offending
+// line with a caret under the failing column and what the grammar wanted there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub col: usize,
+    pub width: usize,
+    pub snippet: String,
+    pub message: String,
+    pub expected: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn from_pest<R: RuleType>(err: Error<R>) -> Diagnostic {
+        let snippet = err.line().trim_end_matches(['\r', '\n']).to_string();
+        let (line, col, width) = match err.line_col {
+            LineColLocation::Pos((l, c)) => (l, c, 1),
+            LineColLocation::Span((l1, c1), (l2, c2)) if l1 == l2 => (l1, c1, c2.saturating_sub(c1).max(1)),
+            LineColLocation::Span((l, c), _) => (l, c, 1),
+        };
+
+        let (message, expected) = match &err.variant {
+            ErrorVariant::ParsingError { positives, negatives } => {
+                let message = match negatives.as_slice() {
+                    [] => "unexpected input".to_string(),
+                    rules => format!("unexpected {}", rule_names(rules).join(", ")),
+                };
+                (message, rule_names(positives))
+            },
+            ErrorVariant::CustomError { message } => (message.clone(), Vec::new()),
+        };
+
+        Diagnostic { line, col, width, snippet, message, expected }
+    }
+}
+
+impl<R: RuleType> From<Error<R>> for Diagnostic {
+    fn from(err: Error<R>) -> Self {
+        Diagnostic::from_pest(err)
+    }
+}
+
+fn rule_names<R: RuleType>(rules: &[R]) -> Vec<String> {
+    rules.iter().map(|r| format!("{:?}", r)).collect()
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "error: {} at line {}, column {}", self.message, self.line, self.col)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(f, "{} | {}{}", gutter, " ".repeat(self.col.saturating_sub(1)), "^".repeat(self.width))?;
+        if !self.expected.is_empty() {
+            write!(f, "\n{} = expected one of: {}", gutter, self.expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Every malformed record of an input, reported together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, d) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", d)?;
+        }
+        write!(f, "{} malformed record(s)", self.0.len())
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use serde::{Deserialize, Serialize};
+
+// POINT ----------------------------------------
+
+// Screen coordinates: x grows to the east, y grows to the south.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+// The 3x3 block around a point row by row, without the point itself.
+pub const NEIGHBORS_8: [Point; 8] = [
+    Point::new(-1, -1), Point::new(0, -1), Point::new(1, -1),
+    Point::new(-1, 0), Point::new(1, 0),
+    Point::new(-1, 1), Point::new(0, 1), Point::new(1, 1),
+];
+
+impl Point {
+    pub const fn new(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    // No day measures distances on a grid yet.
+    #[allow(dead_code)]
+    pub fn manhattan(self, other: Point) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn step(self, dir: Direction) -> Point {
+        self + dir.offset()
+    }
+
+    // North, east, south then west.
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        Direction::ALL.into_iter().map(move |d| self.step(d))
+    }
+
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        NEIGHBORS_8.into_iter().map(move |o| self + o)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, rhs: i64) -> Point {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+// DIRECTION ------------------------------------
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+// A turn instruction, relative to the current heading.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+impl Direction {
+    // Clockwise from north.
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn offset(self) -> Point {
+        match self {
+            Direction::North => Point::new(0, -1),
+            Direction::East => Point::new(1, 0),
+            Direction::South => Point::new(0, 1),
+            Direction::West => Point::new(-1, 0),
+        }
+    }
+
+    // The direction of a single step, None for anything else.
+    pub fn from_offset(offset: Point) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|d| d.offset() == offset)
+    }
+
+    pub fn turn_right(self) -> Direction {
+        Direction::ALL[(self as usize + 1) % 4]
+    }
+
+    // Day 10 keeps the loop clockwise, so it only ever looks right.
+    #[allow(dead_code)]
+    pub fn turn_left(self) -> Direction {
+        Direction::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn back(self) -> Direction {
+        Direction::ALL[(self as usize + 2) % 4]
+    }
+}
+
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Turn::Left => write!(f, "L"),
+            Turn::Right => write!(f, "R"),
+        }
+    }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_arithmetic() {
+        let p = Point::new(3, -2);
+        assert_eq!(Point::new(4, -4), p + Point::new(1, -2));
+        assert_eq!(Point::new(6, -4), p * 2);
+        assert_eq!(Point::default(), p - p);
+        assert_eq!(9, p.manhattan(Point::new(-1, 3)));
+        assert_eq!(vec![Point::new(3, -3), Point::new(4, -2), Point::new(3, -1), Point::new(2, -2)], p.neighbors4().collect::<Vec<_>>());
+        assert_eq!(8, p.neighbors8().filter(|n| n.manhattan(p) <= 2).count());
+    }
+
+    #[test]
+    fn direction_turns() {
+        for d in Direction::ALL {
+            assert_eq!(d, d.turn_right().turn_left());
+            assert_eq!(d.back(), d.turn_right().turn_right());
+            assert_eq!(-d.offset(), d.back().offset());
+            assert_eq!(Some(d), Direction::from_offset(d.offset()));
+        }
+        assert_eq!(Direction::East, Direction::North.turn_right());
+        assert_eq!(None, Direction::from_offset(Point::new(1, 1)));
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+use pest::{Parser, RuleType};
+use serde::{Deserialize, Serialize};
+use crate::ast;
+use crate::diagnostic::Diagnostic;
+use crate::geom::Point;
+
+// Rectangular grid of cells stored row by row, x being the column and y the row.
+// Points off the grid are fine to ask about, `get` just has nothing there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Grid<T> {
+        assert_eq!(width * height, cells.len(), "grid of {}x{} can't hold {} cells", width, height, cells.len());
+        Grid { width, height, cells }
+    }
+
+    // Parses `content` with `rule` and maps every char of each `row` pair with `f`.
+    // The grammar decides what a cell is, this only checks that the rows all have
+    // the same length.
+    pub fn parse<P: Parser<R>, R: RuleType>(rule: R, row: R, content: &str, f: impl FnMut(char) -> T) -> Result<Grid<T>, Diagnostic> {
+        let rows: Vec<&str> = ast::parse_children::<P, R, _>(rule, row, content)?;
+        let width = rows.first().map_or(0, |r| r.chars().count());
+        for row in &rows {
+            let row_width = row.chars().count();
+            if row_width != width {
+                return Err(ast::slice_error::<R>(content, row, format!("row has {} cells, expected {}", row_width, width)));
+            }
+        }
+
+        let cells = rows.iter().flat_map(|r| r.chars()).map(f).collect();
+        Ok(Grid::new(width, rows.len(), cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    pub fn index(&self, p: Point) -> Option<usize> {
+        self.contains(p).then(|| p.y as usize * self.width + p.x as usize)
+    }
+
+    pub fn point(&self, index: usize) -> Point {
+        Point::new((index % self.width) as i64, (index / self.width) as i64)
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index(p).map(|i| &self.cells[i])
+    }
+
+    // The days so far only read their grids.
+    #[allow(dead_code)]
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index(p).map(|i| &mut self.cells[i])
+    }
+
+    // `Point::neighbors4` that are on the grid.
+    pub fn neighbors4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbors4().filter(|n| self.contains(*n))
+    }
+
+    // `Point::neighbors8` that are on the grid. Day 3 walks the ring around a
+    // whole number instead, so nothing calls this yet.
+    #[allow(dead_code)]
+    pub fn neighbors8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbors8().filter(|n| self.contains(*n))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    // No day reads a grid by column yet.
+    #[allow(dead_code)]
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        let height = if x < self.width { self.height } else { 0 };
+        (0..height).map(move |y| &self.cells[y * self.width + x])
+    }
+
+    #[allow(dead_code)]
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    // Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().enumerate().map(|(i, c)| (self.point(i), c))
+    }
+
+    pub fn find(&self, pred: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.cells.iter().position(pred).map(|i| self.point(i))
+    }
+
+    // Day 10 only has the one start, which `find` gets.
+    #[allow(dead_code)]
+    pub fn positions<'a>(&'a self, mut pred: impl FnMut(&T) -> bool + 'a) -> impl Iterator<Item = Point> + 'a {
+        self.iter().filter(move |(_, c)| pred(c)).map(|(p, _)| p)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use pest_derive::Parser;
+    use super::*;
+
+    #[derive(Parser)]
+    #[grammar_inline = "row = @{ ASCII_ALPHA+ }\nletters = { SOI ~ NEWLINE* ~ (row ~ NEWLINE*)* ~ EOI }"]
+    struct Letters;
+
+    fn letters(text: &str) -> Result<Grid<char>, Diagnostic> {
+        Grid::parse::<Letters, _>(Rule::letters, Rule::row, text, |c| c)
+    }
+
+    static SAMPLE: &str = "
+abc
+def
+";
+
+    #[test]
+    fn parse_and_get() {
+        let grid = letters(SAMPLE).unwrap();
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(Some(&'f'), grid.get(Point::new(2, 1)));
+        assert_eq!(None, grid.get(Point::new(3, 1)));
+        assert_eq!(None, grid.get(Point::new(-1, 0)));
+        assert_eq!(SAMPLE.trim(), grid.to_string());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let d = letters("abc\nde\n").err().unwrap();
+        assert_eq!((2, 1, "row has 2 cells, expected 3"), (d.line, d.col, d.message.as_str()));
+        let d = letters("abc\nd?f\n").err().unwrap();
+        assert_eq!((2, 2), (d.line, d.col));
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = letters(SAMPLE).unwrap();
+        assert_eq!(vec![Point::new(1, 0), Point::new(0, 1)], grid.neighbors4(Point::new(0, 0)).collect::<Vec<_>>());
+        assert_eq!(vec![Point::new(0, 0), Point::new(2, 0), Point::new(0, 1), Point::new(1, 1), Point::new(2, 1)], grid.neighbors8(Point::new(1, 0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rows_columns_and_search() {
+        let mut grid = letters(SAMPLE).unwrap();
+        *grid.get_mut(Point::new(0, 1)).unwrap() = 'a';
+        assert_eq!(vec!["abc", "aef"], grid.rows().map(|r| r.iter().collect::<String>()).collect::<Vec<_>>());
+        assert_eq!(vec!["aa", "be", "cf"], grid.columns().map(|c| c.collect::<String>()).collect::<Vec<_>>());
+        assert_eq!(Some(Point::new(2, 1)), grid.find(|c| *c == 'f'));
+        assert_eq!(vec![Point::new(0, 0), Point::new(0, 1)], grid.positions(|c| *c == 'a').collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+// INTERVAL -------------------------------------
+
+// Half-open `start..end`, empty when `end <= start`. With u64 bounds the value
+// u64::MAX itself can't be covered, which keeps every length a u64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Interval<T = u64> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Ord + Copy> Interval<T> {
+    pub fn new(start: T, end: T) -> Interval<T> {
+        Interval { start, end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value < self.end
+    }
+
+    pub fn intersection(&self, other: &Interval<T>) -> Interval<T> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // The part below `at` and the part from `at` on, either may be empty.
+    pub fn split(&self, at: T) -> (Interval<T>, Interval<T>) {
+        let at = at.clamp(self.start, self.end.max(self.start));
+        (Interval::new(self.start, at), Interval::new(at, self.end))
+    }
+}
+
+impl Interval<u64> {
+    // None when `start + len` overflows.
+    pub fn with_len(start: u64, len: u64) -> Option<Interval<u64>> {
+        Some(Interval::new(start, start.checked_add(len)?))
+    }
+
+    // None when the shifted interval would leave 0..=u64::MAX.
+    pub fn shift(&self, delta: i128) -> Option<Interval<u64>> {
+        if self.is_empty() {
+            return Some(*self);
+        }
+        let start = u64::try_from(self.start as i128 + delta).ok()?;
+        let end = u64::try_from(self.end as i128 + delta).ok()?;
+        Some(Interval::new(start, end))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+// RANGE SET ------------------------------------
+
+// Set of values kept as sorted, disjoint, non-adjacent and non-empty intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RangeSet<T = u64> {
+    spans: Vec<Interval<T>>,
+}
+
+impl<T: Ord + Copy> RangeSet<T> {
+    pub fn new() -> RangeSet<T> {
+        RangeSet { spans: Vec::new() }
+    }
+
+    // Sorts and merges overlapping or touching intervals, dropping empty ones.
+    fn normalized(mut spans: Vec<Interval<T>>) -> RangeSet<T> {
+        spans.retain(|s| !s.is_empty());
+        spans.sort_by_key(|s| s.start);
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(spans.len());
+        for s in spans {
+            match merged.last_mut() {
+                Some(last) if s.start <= last.end => last.end = last.end.max(s.end),
+                _ => merged.push(s),
+            }
+        }
+        RangeSet { spans: merged }
+    }
+
+    // Day 5 only asks for the smallest value, through `min`.
+    #[allow(dead_code)]
+    pub fn spans(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.spans.iter()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.spans.first().map(|s| s.start)
+    }
+
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        RangeSet::normalized(self.spans.iter().chain(other.spans.iter()).copied().collect())
+    }
+
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut spans = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.spans.len() && j < other.spans.len() {
+            let (a, b) = (self.spans[i], other.spans[j]);
+            let both = a.intersection(&b);
+            if !both.is_empty() {
+                spans.push(both);
+            }
+            if a.end < b.end { i += 1 } else { j += 1 }
+        }
+        RangeSet { spans }
+    }
+
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut spans = Vec::new();
+        let mut j = 0;
+        for a in &self.spans {
+            let mut rest = *a;
+            while j < other.spans.len() && other.spans[j].end <= rest.start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.spans.len() && other.spans[k].start < rest.end {
+                let (below, _) = rest.split(other.spans[k].start);
+                let (_, above) = rest.split(other.spans[k].end);
+                if !below.is_empty() {
+                    spans.push(below);
+                }
+                rest = above;
+                k += 1;
+            }
+            if !rest.is_empty() {
+                spans.push(rest);
+            }
+        }
+        RangeSet { spans }
+    }
+
+    // The values below `at` and the ones from `at` on. Day 5 cuts its ids along
+    // whole map ranges with `intersection` and `difference` instead.
+    #[allow(dead_code)]
+    pub fn split(&self, at: T) -> (RangeSet<T>, RangeSet<T>) {
+        let (mut below, mut above) = (Vec::new(), Vec::new());
+        for s in &self.spans {
+            let (b, a) = s.split(at);
+            if !b.is_empty() {
+                below.push(b);
+            }
+            if !a.is_empty() {
+                above.push(a);
+            }
+        }
+        (RangeSet { spans: below }, RangeSet { spans: above })
+    }
+}
+
+impl RangeSet<u64> {
+    // None when a value would leave 0..=u64::MAX.
+    pub fn shift(&self, delta: i128) -> Option<RangeSet<u64>> {
+        let spans = self.spans.iter().map(|s| s.shift(delta)).collect::<Option<Vec<_>>>()?;
+        Some(RangeSet { spans })
+    }
+}
+
+impl<T: Ord + Copy> From<Interval<T>> for RangeSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        RangeSet::normalized(vec![interval])
+    }
+}
+
+impl<T: Ord + Copy> FromIterator<Interval<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        RangeSet::normalized(iter.into_iter().collect())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, s) in self.spans.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", s)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(spans: &[(u64, u64)]) -> RangeSet {
+        spans.iter().map(|(s, e)| Interval::new(*s, *e)).collect()
+    }
+
+    #[test]
+    fn interval_basics() {
+        let i = Interval::new(3u64, 8);
+        assert!(i.contains(3) && !i.contains(8));
+        assert_eq!((Interval::new(3, 5), Interval::new(5, 8)), i.split(5));
+        assert_eq!((Interval::new(3, 3), i), i.split(0));
+        assert!(Interval::new(5u64, 5).is_empty());
+        assert!(i.intersection(&Interval::new(8, 10)).is_empty());
+    }
+
+    #[test]
+    fn normalizes() {
+        let s = set(&[(10, 12), (0, 3), (3, 5), (4, 6), (7, 7)]);
+        assert_eq!("{0..6, 10..12}", s.to_string());
+        assert_eq!(vec![Interval::new(0, 6), Interval::new(10, 12)], s.spans().copied().collect::<Vec<_>>());
+        assert_eq!(Some(0), s.min());
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25), (28, 40)]);
+        assert_eq!(set(&[(0, 40)]), a.union(&b));
+        assert_eq!(set(&[(5, 10), (20, 25), (28, 30)]), a.intersection(&b));
+        assert_eq!(set(&[(0, 5), (25, 28)]), a.difference(&b));
+        assert_eq!(set(&[(10, 20), (30, 40)]), b.difference(&a));
+        assert_eq!((set(&[(0, 10), (20, 22)]), set(&[(22, 30)])), a.split(22));
+    }
+
+    #[test]
+    fn overflow_at_max() {
+        assert_eq!(None, Interval::with_len(u64::MAX - 1, 2));
+        let top = Interval::with_len(u64::MAX - 2, 2).unwrap();
+        assert_eq!(Interval::new(u64::MAX - 2, u64::MAX), top);
+        assert_eq!(None, top.shift(1));
+        assert_eq!(Some(Interval::new(0, 2)), top.shift(-(u64::MAX as i128 - 2)));
+        assert_eq!(None, RangeSet::from(top).shift(-(u64::MAX as i128)));
+        let all = RangeSet::from(Interval::new(0, u64::MAX));
+        assert_eq!(set(&[(0, 1), (u64::MAX - 1, u64::MAX)]), all.difference(&set(&[(1, u64::MAX - 1)])));
+    }
+}
@@ -1,11 +1,58 @@
 #[macro_export]
 macro_rules! make_days_map {
     ($fn_name: ident, {$($es:expr => $mod:ident),*}) => (
-        fn $fn_name(day: u8, part: u8, data: String) {
+        fn $fn_name(day: u8, part: u8, data: String, options: &$crate::options::Options) -> Result<(), String> {
             match (day, part) {
-                $(($es, 1u8) => {$mod::part1(data);}, ($es, 2u8) => {$mod::part2(data);},)*
+                $(($es, 1u8) => $mod::part1(data, options), ($es, 2u8) => $mod::part2(data, options),)*
                 (_, _) => panic!("Couldn't find day for ({}, {})", day, part)
-            };
+            }
         }
     )
-}
\ No newline at end of file
+}
+
+#[macro_export]
+macro_rules! make_lints_map {
+    ($fn_name: ident, {$($es:expr => $mod:ident),*}) => (
+        fn $fn_name(day: u8, data: &str) -> Vec<String> {
+            match day {
+                $($es => $mod::lint(data),)*
+                _ => panic!("Couldn't find day {}", day)
+            }
+        }
+    )
+}
+
+#[macro_export]
+macro_rules! make_dumps_map {
+    ($fn_name: ident, {$($es:expr => $mod:ident),*}) => (
+        fn $fn_name(day: u8, data: &str, options: &$crate::options::Options, json: bool) -> Result<String, String> {
+            match day {
+                $($es => $mod::dump(data, options, json),)*
+                _ => panic!("Couldn't find day {}", day)
+            }
+        }
+    )
+}
+
+// Implements `ast::FromPair` for a struct by mapping each field to the child
+// rule it is built from, e.g. `from_pair!(Rule => Cubes<'i> { count: one(cubes_count) })`.
+// Kinds are `one` and `many`; children of other rules are ignored.
+#[macro_export]
+macro_rules! from_pair {
+    ($rule:ident => $name:ident $(<$lt:lifetime>)? { $($field:ident: $kind:ident($variant:ident)),* $(,)? }) => (
+        impl<'i> $crate::ast::FromPair<'i, $rule> for $name $(<$lt>)? {
+            fn from_pair(pair: pest::iterators::Pair<'i, $rule>) -> Result<Self, $crate::diagnostic::Diagnostic> {
+                let span = pair.as_span();
+                $(let mut $field = Vec::new();)*
+                for inner in pair.into_inner() {
+                    $(if inner.as_rule() == $rule::$variant {
+                        $field.push($crate::ast::FromPair::from_pair(inner)?);
+                        continue;
+                    })*
+                }
+
+                Ok($name { $($field: $crate::ast::$kind::<$rule, _>($field, stringify!($field), span)?),* })
+            }
+        }
+    )
+}
@@ -1,6 +1,19 @@
 mod day1;
 mod macros;
+mod diagnostic;
+mod ast;
+mod options;
+mod answer;
+mod geom;
+mod grid;
+mod math;
+mod search;
+mod interval;
+mod memo;
+mod cache;
+mod aho_corasick;
 mod day2;
+mod day2_query;
 mod day3;
 mod day4;
 mod day5;
@@ -13,15 +26,75 @@ mod day10;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use clap::Parser;
+use std::process::ExitCode;
+use clap::{Parser, Subcommand};
+use options::Options;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Optional name to operate on
-    day: u8,
-    part: u8,
-    data: String,
+    #[arg(required = true)]
+    day: Option<u8>,
+    #[arg(required = true)]
+    part: Option<u8>,
+    #[arg(required = true)]
+    data: Option<String>,
+    #[command(flatten)]
+    options: Options,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check a day's input against the assumptions its solutions rely on
+    Lint {
+        day: u8,
+        #[arg(default_value = "data")]
+        data: String,
+    },
+    /// Print the typed structure a day builds out of its input
+    Parse {
+        day: u8,
+        #[arg(default_value = "data")]
+        data: String,
+        /// Print as JSON instead of the Debug tree
+        #[arg(long)]
+        json: bool,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Print the games matching a filter expression, like `blue > 10 in any set` (day 2)
+    Query {
+        day: u8,
+        expr: String,
+        #[arg(default_value = "data")]
+        data: String,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Rank the day 2 bags most likely to have produced the games
+    Infer {
+        #[arg(default_value = "data")]
+        data: String,
+        /// How many bags to list
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Solve day 1 from a file of any size, read in chunks scanned in parallel
+    Stream {
+        part: u8,
+        file: PathBuf,
+        /// Bytes per chunk, about one chunk per thread is held at a time
+        #[arg(long, default_value_t = 1 << 20)]
+        chunk_size: usize,
+        #[command(flatten)]
+        options: Options,
+    },
 }
 
 fn get_content(day: u8, data: String) -> String {
@@ -45,7 +118,120 @@ make_days_map!(run_day, {
     10 => day10
 });
 
-fn main() {
+make_lints_map!(lint_day, {
+    1 => day1,
+    2 => day2,
+    3 => day3,
+    4 => day4,
+    5 => day5,
+    6 => day6,
+    7 => day7,
+    8 => day8,
+    9 => day9,
+    10 => day10
+});
+
+make_dumps_map!(dump_day, {
+    1 => day1,
+    2 => day2,
+    3 => day3,
+    4 => day4,
+    5 => day5,
+    6 => day6,
+    7 => day7,
+    8 => day8,
+    9 => day9,
+    10 => day10
+});
+
+fn dump(day: u8, data: String, options: &Options, json: bool) -> ExitCode {
+    match dump_day(day, &get_content(day, data), options, json) {
+        Ok(text) => {
+            println!("{}", text);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn lint(day: u8, data: String) -> ExitCode {
+    let violations = lint_day(day, &get_content(day, data));
+    for v in &violations {
+        println!("{}\n", v);
+    }
+
+    if violations.is_empty() {
+        println!("day {}: input looks fine", day);
+        ExitCode::SUCCESS
+    } else {
+        println!("day {}: {} assumption(s) violated", day, violations.len());
+        ExitCode::FAILURE
+    }
+}
+
+fn query(day: u8, expr: &str, data: String, options: &Options) -> ExitCode {
+    let result = match day {
+        2 => day2::query(&get_content(day, data), expr, options),
+        _ => Err(format!("day {} has no query language", day)),
+    };
+    match result {
+        Ok(text) => {
+            print!("{}", text);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn infer(data: String, top: usize, options: &Options) -> ExitCode {
+    match day2::infer(&get_content(2, data), options, top) {
+        Ok(text) => {
+            print!("{}", text);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn stream(part: u8, file: PathBuf, chunk_size: usize, options: &Options) -> ExitCode {
+    let solved = File::open(&file)
+        .map_err(|e| format!("{}: {}", file.display(), e))
+        .and_then(|f| day1::stream(part, f, chunk_size, options));
+    match solved {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
-    run_day(args.day, args.part, get_content(args.day, args.data));
+    match args.command {
+        Some(Command::Lint { day, data }) => lint(day, data),
+        Some(Command::Parse { day, data, json, options }) => dump(day, data, &options, json),
+        Some(Command::Query { day, expr, data, options }) => query(day, &expr, data, &options),
+        Some(Command::Infer { data, top, options }) => infer(data, top, &options),
+        Some(Command::Stream { part, file, chunk_size, options }) => stream(part, file, chunk_size, &options),
+        None => {
+            let day = args.day.unwrap();
+            match run_day(day, args.part.unwrap(), get_content(day, args.data.unwrap()), &args.options) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,150 @@
+use std::fmt;
+
+// GCD / LCM ------------------------------------
+
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// None when the result doesn't fit in a u64. Day 8 needs the offsets too, which
+// `crt` works out along with the common period.
+#[allow(dead_code)]
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// Returns (g, x, y) with a*x + b*y = g = gcd(a, b), wide enough for any pair of u64.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+// x in [0, m) with a*x = 1 (mod m), None when a and m aren't coprime.
+pub fn mod_inverse(a: u64, m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd((a % m) as i128, m as i128);
+    (g == 1).then(|| x.rem_euclid(m as i128) as u64)
+}
+
+// CRT ------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrtError {
+    // The congruence at `index` contradicts the ones before it.
+    Unsatisfiable { index: usize },
+    // The combined modulus doesn't fit in a u64.
+    Overflow,
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtError::Unsatisfiable { index } => write!(f, "congruence #{} contradicts the ones before it", index + 1),
+            CrtError::Overflow => write!(f, "combined modulus overflows u64"),
+        }
+    }
+}
+
+// Solves x = r (mod m) for every (r, m), moduli don't have to be coprime. The
+// solutions are exactly x = r (mod m) for the returned (r, m), m being the lcm
+// of all moduli. Moduli must be non-zero.
+pub fn crt(congruences: &[(u64, u64)]) -> Result<(u64, u64), CrtError> {
+    let mut acc = (0u64, 1u64);
+    for (index, (r, m)) in congruences.iter().enumerate() {
+        assert!(*m > 0, "congruence #{} has a zero modulus", index + 1);
+        acc = crt_merge(acc, (r % m, *m)).ok_or(CrtError::Unsatisfiable { index })??;
+    }
+    Ok(acc)
+}
+
+// None when unsatisfiable, Some(Err) on overflow.
+fn crt_merge((r1, m1): (u64, u64), (r2, m2): (u64, u64)) -> Option<Result<(u64, u64), CrtError>> {
+    let g = gcd(m1, m2);
+    let diff = r2 as i128 - r1 as i128;
+    if diff % g as i128 != 0 {
+        return None;
+    }
+    let Some(m) = (m1 / g).checked_mul(m2) else {
+        return Some(Err(CrtError::Overflow));
+    };
+
+    // r1 + m1*t = r2 (mod m2)  <=>  (m1/g)*t = diff/g (mod m2/g)
+    let m2g = m2 / g;
+    let inv = mod_inverse(m1 / g, m2g).unwrap_or(0);
+    let d = (diff / g as i128).rem_euclid(m2g as i128) as u128;
+    let t = (d * inv as u128 % m2g as u128) as u64;
+    // m1*t < m1*(m2/g) = m, so this fits; the sum is reduced in u128
+    let r = ((r1 as u128 + m1 as u128 * t as u128) % m as u128) as u64;
+    Some(Ok((r, m)))
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_lcm() {
+        assert_eq!(6, gcd(54, 24));
+        assert_eq!(7, gcd(0, 7));
+        assert_eq!(Some(216), lcm(54, 24));
+        assert_eq!(Some(0), lcm(0, 5));
+        assert_eq!(None, lcm(u64::MAX, u64::MAX - 1));
+        assert_eq!(Some(u64::MAX), lcm(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn extended_euclid_and_inverse() {
+        let (g, x, y) = extended_gcd(240, 46);
+        assert_eq!(2, g);
+        assert_eq!(2, 240 * x + 46 * y);
+        assert_eq!(Some(4), mod_inverse(3, 11));
+        assert_eq!(None, mod_inverse(4, 12));
+        let m = u64::MAX - 58; // largest prime below 2^64
+        let inv = mod_inverse(u64::MAX - 100, m).unwrap();
+        assert_eq!(1, ((u64::MAX - 100) as u128 * inv as u128 % m as u128) as u64);
+    }
+
+    #[test]
+    fn crt_coprime() {
+        assert_eq!(Ok((23, 105)), crt(&[(2, 3), (3, 5), (2, 7)]));
+        assert_eq!(Ok((0, 1)), crt(&[]));
+    }
+
+    #[test]
+    fn crt_non_coprime() {
+        assert_eq!(Ok((10, 12)), crt(&[(4, 6), (2, 4)]));
+        assert_eq!(Ok((3, 6)), crt(&[(1, 2), (3, 6), (0, 3)]));
+        assert_eq!(Err(CrtError::Unsatisfiable { index: 1 }), crt(&[(1, 2), (2, 4)]));
+    }
+
+    #[test]
+    fn crt_overflow() {
+        let p = u64::MAX - 58;
+        assert_eq!(Ok((5, p)), crt(&[(5, p), (5 + p, p)]));
+        assert_eq!(Err(CrtError::Overflow), crt(&[(1, p), (1, 3)]));
+        let q = 4294967291; // largest prime below 2^32
+        assert_eq!(Ok((q - 1, q * q)), crt(&[(q - 1, q), (q - 1, q * q)]));
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+// STATS ----------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Stats {
+    // Share of lookups answered from the cache, 0 before the first one.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hits, {} misses ({:.1}% hit rate)", self.hits, self.misses, 100.0 * self.hit_rate())
+    }
+}
+
+// MEMO -----------------------------------------
+
+// Results of a function by argument. The function gets the memo back so its
+// recursive calls go through the cache too:
+//
+//     fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+//         memo.get(&n, |memo, n| if *n < 2 { *n } else { fib(memo, n - 1) + fib(memo, n - 2) })
+//     }
+#[derive(Debug, Clone)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    stats: Stats,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Memo<K, V> {
+    pub fn new() -> Memo<K, V> {
+        Memo { cache: HashMap::new(), stats: Stats::default() }
+    }
+
+    // The key is only cloned to store it, on a miss.
+    pub fn get(&mut self, key: &K, compute: impl FnOnce(&mut Self, &K) -> V) -> V {
+        if let Some(value) = self.cache.get(key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+        self.stats.misses += 1;
+        let value = compute(self, key);
+        self.cache.insert(key.clone(), value.clone());
+        value
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // Number of distinct arguments seen.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Memo::new()
+    }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+        memo.get(&n, |memo, n| if *n < 2 { *n } else { fib(memo, n - 1) + fib(memo, n - 2) })
+    }
+
+    #[test]
+    fn caches_recursive_calls() {
+        let mut memo = Memo::new();
+        assert_eq!(12586269025, fib(&mut memo, 50));
+        // every n in 0..=50 computed once, fib(n - 2) found for n in 3..=50
+        assert_eq!(Stats { hits: 48, misses: 51 }, memo.stats());
+        assert_eq!(51, memo.len());
+        assert_eq!(12586269025, fib(&mut memo, 50));
+        assert_eq!(Stats { hits: 49, misses: 51 }, memo.stats());
+        assert_eq!("49 hits, 51 misses (49.0% hit rate)", memo.stats().to_string());
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+use clap::{Args, ValueEnum};
+
+// Flags shared by every day, passed down to `part1`/`part2`.
+#[derive(Args, Debug, Default, Clone)]
+pub struct Options {
+    /// Skip malformed records with a warning instead of failing (days 2, 4, 7 and 9)
+    #[arg(long)]
+    pub lenient: bool,
+    /// Report the hand type cache statistics of day 7 and the throughput of `stream` (day 1) on stderr
+    #[arg(long)]
+    pub verbose: bool,
+    /// Keep parsed inputs in this directory and reuse them on later runs (days 8 and 10)
+    #[arg(long, value_name = "DIR")]
+    pub cache: Option<PathBuf>,
+    /// Digit words for day 1 part 2: english, french, german, roman or a file of `token digit` lines
+    #[arg(long, value_name = "NAME|FILE")]
+    pub vocabulary: Option<String>,
+    /// Show what each line or game amounts to before the result (days 1 and 2)
+    #[arg(long)]
+    pub explain: bool,
+    /// Cubes in the day 2 bag, as `color=count` pairs [default: red=12,green=13,blue=14]
+    #[arg(long, value_name = "COLOR=COUNT,...")]
+    pub bag: Option<String>,
+    /// Check every bag of this file, one `color=count,...` line each, instead of `--bag` (day 2)
+    #[arg(long, value_name = "FILE", conflicts_with = "bag")]
+    pub bags: Option<PathBuf>,
+    /// What day 2 does with a color the bag doesn't list
+    #[arg(long, value_enum, default_value_t)]
+    pub unknown_color: UnknownColor,
+    /// How day 2 part 2 turns a minimum bag into a power: product, sum, max or weighted:color=weight,...
+    #[arg(long, value_name = "STRATEGY")]
+    pub power: Option<String>,
+    /// The count day 2 part 2 uses for a color a game never shows
+    #[arg(long, value_enum, default_value_t)]
+    pub absent: Absent,
+}
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Absent {
+    /// Counts as 0, zeroing products
+    Zero,
+    /// Counts as 1, leaving products alone
+    #[default]
+    One,
+}
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq)]
+pub enum UnknownColor {
+    /// The bag has none of it, so a game showing any is impossible
+    Reject,
+    /// Leave the color out of the check
+    Ignore,
+    /// Fail
+    #[default]
+    Error,
+}
@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+// PATHS ----------------------------------------
+
+// Everything reached from the start, with its distance and the node it was reached from.
+#[derive(Debug, Clone)]
+pub struct Paths<N> {
+    distances: HashMap<N, u64>,
+    parents: HashMap<N, N>,
+}
+
+impl<N: Eq + Hash + Clone> Paths<N> {
+    pub fn distance(&self, node: &N) -> Option<u64> {
+        self.distances.get(node).copied()
+    }
+
+    // From the start to `node`, both included.
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.distances.contains_key(node) {
+            return None;
+        }
+        let mut path = vec![node.clone()];
+        while let Some(parent) = self.parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// BFS / DIJKSTRA / A* --------------------------
+
+// Every edge counts for one step. Day 10's fill spreads from many tiles at
+// once, which this doesn't do, so nothing calls it yet.
+#[allow(dead_code)]
+pub fn bfs<N, I>(start: N, mut neighbors: impl FnMut(&N) -> I) -> Paths<N>
+where N: Eq + Hash + Clone, I: IntoIterator<Item = N> {
+    let mut paths = Paths { distances: HashMap::from([(start.clone(), 0)]), parents: HashMap::new() };
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        let d = paths.distances[&node];
+        for next in neighbors(&node) {
+            if let Entry::Vacant(e) = paths.distances.entry(next.clone()) {
+                e.insert(d + 1);
+                paths.parents.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    paths
+}
+
+// `neighbors` gives each next node with the cost of the edge to it. No puzzle
+// so far has weighted edges.
+#[allow(dead_code)]
+pub fn dijkstra<N, I>(start: N, neighbors: impl FnMut(&N) -> I) -> Paths<N>
+where N: Eq + Hash + Clone, I: IntoIterator<Item = (N, u64)> {
+    best_first(start, neighbors, |_| 0, |_| false).0
+}
+
+// Dijkstra guided by `heuristic`, which must never overestimate the cost left to
+// a goal. Returns the path to the first goal reached and its cost. Unused until
+// a puzzle asks for a shortest route to somewhere.
+#[allow(dead_code)]
+pub fn astar<N, I>(start: N, neighbors: impl FnMut(&N) -> I, heuristic: impl FnMut(&N) -> u64, is_goal: impl FnMut(&N) -> bool) -> Option<(Vec<N>, u64)>
+where N: Eq + Hash + Clone, I: IntoIterator<Item = (N, u64)> {
+    let (paths, goal) = best_first(start, neighbors, heuristic, is_goal);
+    let goal = goal?;
+    Some((paths.path_to(&goal)?, paths.distance(&goal)?))
+}
+
+fn best_first<N, I>(start: N, mut neighbors: impl FnMut(&N) -> I, mut heuristic: impl FnMut(&N) -> u64, mut is_goal: impl FnMut(&N) -> bool) -> (Paths<N>, Option<N>)
+where N: Eq + Hash + Clone, I: IntoIterator<Item = (N, u64)> {
+    let mut paths = Paths { distances: HashMap::from([(start.clone(), 0)]), parents: HashMap::new() };
+    // nodes live in `queued` so the heap doesn't need them to be Ord
+    let mut queued = vec![start.clone()];
+    let mut heap = BinaryHeap::from([Reverse((heuristic(&start), 0u64, 0usize))]);
+    while let Some(Reverse((_, d, i))) = heap.pop() {
+        let node = queued[i].clone();
+        if d > paths.distances[&node] {
+            continue;
+        }
+        if is_goal(&node) {
+            return (paths, Some(node));
+        }
+        for (next, cost) in neighbors(&node) {
+            let nd = d + cost;
+            if paths.distances.get(&next).is_some_and(|old| *old <= nd) {
+                continue;
+            }
+            paths.distances.insert(next.clone(), nd);
+            paths.parents.insert(next.clone(), node.clone());
+            heap.push(Reverse((nd + heuristic(&next), nd, queued.len())));
+            queued.push(next);
+        }
+    }
+    (paths, None)
+}
+
+// CYCLES ---------------------------------------
+
+// The sequence x0, f(x0), f(f(x0)), ... enters a loop after `start` steps and
+// repeats every `length` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+// Brent's algorithm: about as many calls to `next` as Floyd's, no storage.
+pub fn brent<S: PartialEq + Clone>(x0: S, mut next: impl FnMut(&S) -> S) -> Cycle {
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = x0.clone();
+    let mut hare = next(&x0);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = next(&hare);
+        length += 1;
+    }
+
+    // a hare `length` steps ahead meets the tortoise right where the loop starts
+    let mut tortoise = x0.clone();
+    let mut hare = x0;
+    for _ in 0..length {
+        hare = next(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = next(&tortoise);
+        hare = next(&hare);
+        start += 1;
+    }
+
+    Cycle { start, length }
+}
+
+// Floyd's tortoise and hare. Day 8 uses `brent`, this one backs it up in the tests.
+#[allow(dead_code)]
+pub fn floyd<S: PartialEq + Clone>(x0: S, mut next: impl FnMut(&S) -> S) -> Cycle {
+    let mut tortoise = next(&x0);
+    let mut hare = next(&tortoise);
+    while tortoise != hare {
+        tortoise = next(&tortoise);
+        hare = next(&hare);
+        hare = next(&hare);
+    }
+
+    let mut start = 0;
+    let mut tortoise = x0;
+    while tortoise != hare {
+        tortoise = next(&tortoise);
+        hare = next(&hare);
+        start += 1;
+    }
+
+    let mut length = 1;
+    let mut hare = next(&tortoise);
+    while tortoise != hare {
+        hare = next(&hare);
+        length += 1;
+    }
+
+    Cycle { start, length }
+}
+
+// TESTS ----------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 -> 1 -> 2 -> 3 and a shortcut 0 -> 2, 4 unreachable
+    fn edges(n: &u32) -> Vec<(u32, u64)> {
+        match n {
+            0 => vec![(1, 1), (2, 5)],
+            1 => vec![(2, 1)],
+            2 => vec![(3, 1)],
+            4 => vec![(0, 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn bfs_counts_steps() {
+        let paths = bfs(0, |n| edges(n).into_iter().map(|(m, _)| m));
+        assert_eq!(Some(1), paths.distance(&2));
+        assert_eq!(Some(vec![0, 2, 3]), paths.path_to(&3));
+        assert_eq!(None, paths.path_to(&4));
+        assert_eq!(None, paths.distance(&4));
+    }
+
+    #[test]
+    fn dijkstra_and_astar_weigh_edges() {
+        let paths = dijkstra(0, edges);
+        assert_eq!(Some(2), paths.distance(&2));
+        assert_eq!(Some(vec![0, 1, 2, 3]), paths.path_to(&3));
+        assert_eq!(Some((vec![0, 1, 2, 3], 3)), astar(0, edges, |n| 3 - (*n).min(3) as u64, |n| *n == 3));
+        assert_eq!(None, astar(0, edges, |_| 0, |n| *n == 4));
+    }
+
+    #[test]
+    fn cycles() {
+        // 3 -> 10 -> 5 -> 16 -> 8 -> 4 -> 2 -> 1 -> 4 ...
+        let collatz = |n: &u64| if n.is_multiple_of(2) { n / 2 } else { 3 * n + 1 };
+        assert_eq!(Cycle { start: 5, length: 3 }, brent(3, collatz));
+        assert_eq!(Cycle { start: 5, length: 3 }, floyd(3, collatz));
+        assert_eq!(Cycle { start: 0, length: 7 }, brent(0, |n| (n + 1) % 7));
+        assert_eq!(Cycle { start: 0, length: 1 }, floyd(0, |n| *n));
+    }
+}